@@ -0,0 +1,109 @@
+// compares reading from a pty through the AsyncFd based Pty with the
+// io_uring based one. the child process writes output as fast as it can, so
+// this measures the overhead of reading it rather than the child itself.
+
+const TOTAL: usize = 1024 * 1024;
+const CHUNK: usize = 64 * 1024;
+
+async fn spawn(pts: &pty_process::Pts) -> tokio::process::Child {
+    pty_process::Command::new("cat")
+        .arg("/dev/zero")
+        .spawn(pts)
+        .unwrap()
+}
+
+async fn kill(mut child: tokio::process::Child) {
+    child.start_kill().unwrap();
+    child.wait().await.unwrap();
+}
+
+fn bench_asyncfd(b: &mut criterion::Bencher) {
+    use tokio::io::AsyncReadExt as _;
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    b.iter_custom(|iters| {
+        rt.block_on(async {
+            let mut pty = pty_process::Pty::new().unwrap();
+            let child = spawn(&pty.pts().unwrap()).await;
+            let mut buf = vec![0u8; CHUNK];
+            let start = std::time::Instant::now();
+            for _ in 0..iters {
+                let mut total = 0;
+                while total < TOTAL {
+                    total += pty.read(&mut buf).await.unwrap();
+                }
+            }
+            let elapsed = start.elapsed();
+            kill(child).await;
+            elapsed
+        })
+    });
+}
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+fn bench_uring(b: &mut criterion::Bencher) {
+    b.iter_custom(|iters| {
+        tokio_uring::start(async {
+            let pty = pty_process::uring::Pty::new().unwrap();
+            let child = spawn(&pty.pts().unwrap()).await;
+            let mut buf = Vec::with_capacity(CHUNK);
+            let start = std::time::Instant::now();
+            for _ in 0..iters {
+                let mut total = 0;
+                while total < TOTAL {
+                    let (res, next) = pty.read(buf).await;
+                    total += res.unwrap();
+                    buf = next;
+                }
+            }
+            let elapsed = start.elapsed();
+            kill(child).await;
+            elapsed
+        })
+    });
+}
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+fn bench_uring_fixed(b: &mut criterion::Bencher) {
+    b.iter_custom(|iters| {
+        tokio_uring::start(async {
+            let registry = tokio_uring::buf::fixed::FixedBufRegistry::new(
+                std::iter::once(Vec::with_capacity(CHUNK)),
+            );
+            registry.register().unwrap();
+            let pty = pty_process::uring::Pty::new().unwrap();
+            let child = spawn(&pty.pts().unwrap()).await;
+            let mut buf = registry.check_out(0).unwrap();
+            let start = std::time::Instant::now();
+            for _ in 0..iters {
+                let mut total = 0;
+                while total < TOTAL {
+                    let (res, next) = pty.read_fixed(buf).await;
+                    total += res.unwrap();
+                    buf = next;
+                }
+            }
+            let elapsed = start.elapsed();
+            kill(child).await;
+            elapsed
+        })
+    });
+}
+
+fn bench(c: &mut criterion::Criterion) {
+    let mut group = c.benchmark_group("read");
+    group.throughput(criterion::Throughput::Bytes(TOTAL.try_into().unwrap()));
+    group.bench_function("asyncfd", bench_asyncfd);
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    {
+        group.bench_function("uring", bench_uring);
+        group.bench_function("uring_fixed", bench_uring_fixed);
+    }
+    group.finish();
+}
+
+criterion::criterion_group!(benches, bench);
+criterion::criterion_main!(benches);
@@ -0,0 +1,91 @@
+// measures reading `yes` output from the async pty. reading via read_buf
+// into spare capacity lets the pty read directly into uninitialized memory,
+// while the initialized variant goes through the previous implementation,
+// which initialized the unfilled part of the buffer before every read.
+
+const TOTAL: usize = 1024 * 1024;
+const CHUNK: usize = 64 * 1024;
+
+// the previous poll_read implementation, on top of the current one
+struct Initialized<'a>(&'a mut pty_process::Pty);
+
+impl tokio::io::AsyncRead for Initialized<'_> {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        let b = buf.initialize_unfilled();
+        let mut inner = tokio::io::ReadBuf::new(b);
+        match std::pin::Pin::new(&mut *self.0).poll_read(cx, &mut inner) {
+            std::task::Poll::Ready(Ok(())) => {}
+            res => return res,
+        }
+        let bytes = inner.filled().len();
+        buf.advance(bytes);
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+fn bench_yes(
+    b: &mut criterion::Bencher,
+    read: impl for<'a> Fn(
+        &'a mut pty_process::Pty,
+        &'a mut bytes::BytesMut,
+    ) -> std::pin::Pin<
+        Box<dyn std::future::Future<Output = usize> + 'a>,
+    >,
+) {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    b.iter_custom(|iters| {
+        rt.block_on(async {
+            let mut pty = pty_process::Pty::new().unwrap();
+            let mut child = pty_process::Command::new("yes")
+                .spawn(&pty.pts().unwrap())
+                .unwrap();
+            let mut buf = bytes::BytesMut::with_capacity(CHUNK);
+            let start = std::time::Instant::now();
+            for _ in 0..iters {
+                let mut total = 0;
+                while total < TOTAL {
+                    buf.clear();
+                    total += read(&mut pty, &mut buf).await;
+                }
+            }
+            let elapsed = start.elapsed();
+            child.start_kill().unwrap();
+            child.wait().await.unwrap();
+            elapsed
+        })
+    });
+}
+
+fn bench(c: &mut criterion::Criterion) {
+    let mut group = c.benchmark_group("yes");
+    group.throughput(criterion::Throughput::Bytes(TOTAL.try_into().unwrap()));
+    group.bench_function("uninit", |b| {
+        bench_yes(b, |pty, buf| {
+            Box::pin(async move {
+                use tokio::io::AsyncReadExt as _;
+
+                pty.read_buf(buf).await.unwrap()
+            })
+        });
+    });
+    group.bench_function("initialized", |b| {
+        bench_yes(b, |pty, buf| {
+            Box::pin(async move {
+                use tokio::io::AsyncReadExt as _;
+
+                Initialized(pty).read_buf(buf).await.unwrap()
+            })
+        });
+    });
+    group.finish();
+}
+
+criterion::criterion_group!(benches, bench);
+criterion::criterion_main!(benches);
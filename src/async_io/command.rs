@@ -0,0 +1,315 @@
+use async_process::unix::CommandExt as _;
+
+/// Wrapper around [`async_process::Command`]
+pub struct Command {
+    inner: async_process::Command,
+    stdin: bool,
+    stdout: bool,
+    stderr: bool,
+    setup: crate::child::Setup,
+}
+
+impl Command {
+    /// See [`async_process::Command::new`]
+    pub fn new<S: AsRef<std::ffi::OsStr>>(program: S) -> Self {
+        let mut inner = std::process::Command::new(program);
+        let setup = crate::child::Setup::new(&mut inner);
+        Self {
+            inner: inner.into(),
+            stdin: false,
+            stdout: false,
+            stderr: false,
+            setup,
+        }
+    }
+
+    /// Creates a command which runs the login shell of `user`, as `login`
+    /// does. The shell is run with an `argv[0]` of its name prefixed with
+    /// `-` (such as `-bash`), in the user's home directory, and with `TERM`
+    /// set to `xterm-256color`. `COLUMNS` and `LINES` are set from the size
+    /// of the pty each time it is spawned. If `user` isn't the current user,
+    /// the shell is run as that user via [`as_user`](Self::as_user), and
+    /// otherwise the same login environment variables are set directly.
+    /// Any of these variables can be overridden via [`env`](Self::env) and
+    /// related methods.
+    ///
+    /// # Errors
+    /// Returns an error if the user can't be looked up.
+    pub fn login_shell(user: impl Into<crate::User>) -> crate::Result<Self> {
+        let shell = crate::login::Shell::new(user.into())?;
+        let mut cmd = Self::new(&shell.program);
+        cmd.arg0(&shell.arg0).current_dir(&shell.dir);
+        if let Some(user) = shell.user {
+            cmd.as_user(user);
+        }
+        cmd.setup.default_env(shell.env);
+        cmd.setup.size_env();
+        Ok(cmd)
+    }
+
+    /// See [`async_process::Command::arg`]
+    pub fn arg<S: AsRef<std::ffi::OsStr>>(&mut self, arg: S) -> &mut Self {
+        self.inner.arg(arg);
+        self
+    }
+
+    /// See [`async_process::Command::args`]
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        self.inner.args(args);
+        self
+    }
+
+    /// See [`async_process::Command::env`]
+    pub fn env<K, V>(&mut self, key: K, val: V) -> &mut Self
+    where
+        K: AsRef<std::ffi::OsStr>,
+        V: AsRef<std::ffi::OsStr>,
+    {
+        self.setup.caller_env(key.as_ref());
+        self.inner.env(key, val);
+        self
+    }
+
+    /// See [`async_process::Command::envs`]
+    pub fn envs<I, K, V>(&mut self, vars: I) -> &mut Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<std::ffi::OsStr>,
+        V: AsRef<std::ffi::OsStr>,
+    {
+        for (key, val) in vars {
+            self.env(key, val);
+        }
+        self
+    }
+
+    /// See [`async_process::Command::env_remove`]
+    pub fn env_remove<K: AsRef<std::ffi::OsStr>>(
+        &mut self,
+        key: K,
+    ) -> &mut Self {
+        self.setup.caller_env(key.as_ref());
+        self.inner.env_remove(key);
+        self
+    }
+
+    /// See [`async_process::Command::env_clear`]
+    pub fn env_clear(&mut self) -> &mut Self {
+        self.setup.caller_env_clear();
+        self.inner.env_clear();
+        self
+    }
+
+    /// See [`async_process::Command::current_dir`]
+    pub fn current_dir<P: AsRef<std::path::Path>>(
+        &mut self,
+        dir: P,
+    ) -> &mut Self {
+        self.inner.current_dir(dir);
+        self
+    }
+
+    /// See [`async_process::Command::stdin`]
+    pub fn stdin<T: Into<std::process::Stdio>>(
+        &mut self,
+        cfg: T,
+    ) -> &mut Self {
+        self.stdin = true;
+        self.inner.stdin(cfg);
+        self
+    }
+
+    /// See [`async_process::Command::stdout`]
+    pub fn stdout<T: Into<std::process::Stdio>>(
+        &mut self,
+        cfg: T,
+    ) -> &mut Self {
+        self.stdout = true;
+        self.inner.stdout(cfg);
+        self
+    }
+
+    /// See [`async_process::Command::stderr`]
+    pub fn stderr<T: Into<std::process::Stdio>>(
+        &mut self,
+        cfg: T,
+    ) -> &mut Self {
+        self.stderr = true;
+        self.inner.stderr(cfg);
+        self
+    }
+
+    /// Executes the command as a child process via
+    /// [`async_process::Command::spawn`] on the given pty. The pty will be
+    /// attached to all of `stdin`, `stdout`, and `stderr` of the child,
+    /// unless those file descriptors were previously overridden through calls
+    /// to [`stdin`](Self::stdin), [`stdout`](Self::stdout), or
+    /// [`stderr`](Self::stderr). The newly created child process will also be
+    /// made the session leader of a new session, and will have the given
+    /// pty set as its controlling terminal (this can be changed via
+    /// [`session_mode`](Self::session_mode)).
+    ///
+    /// # Errors
+    /// Returns an error if we fail to allocate new file descriptors for
+    /// attaching the pty to the child process, or if we fail to spawn the
+    /// child process (see the documentation for
+    /// [`async_process::Command::spawn`]), or if we fail to make the child a
+    /// session leader or set its controlling terminal.
+    pub fn spawn(
+        &mut self,
+        pts: &crate::async_io::Pts,
+    ) -> crate::Result<async_process::Child> {
+        self.inner.envs(self.setup.prepare_env(&pts.0)?);
+        let (stdin, stdout, stderr) = pts.0.setup_subprocess()?;
+
+        if !self.stdin {
+            self.inner.stdin(stdin);
+        }
+        if !self.stdout {
+            self.inner.stdout(stdout);
+        }
+        if !self.stderr {
+            self.inner.stderr(stderr);
+        }
+
+        self.setup.spawn(
+            &pts.0,
+            || self.inner.spawn(),
+            |child| Some(child.id()),
+        )
+    }
+
+    /// See [`async_process::unix::CommandExt::uid`]
+    pub fn uid(&mut self, id: u32) -> &mut Self {
+        self.inner.uid(id);
+        self
+    }
+
+    /// See [`async_process::unix::CommandExt::gid`]
+    pub fn gid(&mut self, id: u32) -> &mut Self {
+        self.inner.gid(id);
+        self
+    }
+
+    /// See [`std::os::unix::process::CommandExt::pre_exec`]
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn pre_exec<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnMut() -> std::io::Result<()> + Send + Sync + 'static,
+    {
+        self.setup.pre_exec(Box::new(f));
+        self
+    }
+
+    /// Makes the child be sent `signal` when the thread which spawned it
+    /// exits (see `PR_SET_PDEATHSIG` in `prctl(2)`), so that it doesn't
+    /// outlive the calling process. If the calling process has already
+    /// exited by the time the child is set up, the signal is sent to the
+    /// child immediately. Note that this is tied to the spawning thread
+    /// rather than the whole process, so it should only be used when
+    /// spawning from a thread which lives as long as the process. (Linux
+    /// only)
+    #[cfg(target_os = "linux")]
+    pub fn kill_on_parent_death(&mut self, signal: i32) -> &mut Self {
+        self.setup.kill_on_parent_death(signal);
+        self
+    }
+
+    /// Sets the soft and hard limits for `resource` in the child via
+    /// `setrlimit`, replacing any limits previously set for `resource`.
+    /// `None` means that the resource is unlimited. Note that an
+    /// unprivileged process can't raise its hard limits, so both limits
+    /// should usually be given.
+    pub fn rlimit(
+        &mut self,
+        resource: crate::Resource,
+        soft: Option<u64>,
+        hard: Option<u64>,
+    ) -> &mut Self {
+        self.setup.rlimit(resource, soft, hard);
+        self
+    }
+
+    /// Sets the file mode creation mask of the child via `umask`.
+    pub fn umask(&mut self, mask: u32) -> &mut Self {
+        self.setup.umask(mask);
+        self
+    }
+
+    /// Adds `increment` to the niceness of the child via `nice` (so higher
+    /// values give it a lower scheduling priority). Note that an
+    /// unprivileged process can't decrease its niceness.
+    pub fn nice(&mut self, increment: i32) -> &mut Self {
+        self.setup.nice(increment);
+        self
+    }
+
+    /// Sets the I/O scheduling class and priority of the child via
+    /// `ioprio_set`. (Linux only)
+    ///
+    /// # Panics
+    /// Panics if the priority level is higher than 7.
+    #[cfg(target_os = "linux")]
+    pub fn io_priority(&mut self, priority: crate::IoPriority) -> &mut Self {
+        self.setup.io_priority(priority);
+        self
+    }
+
+    /// Runs the child as `user`, as `login` does. The user's passwd entry
+    /// is looked up when spawning, and the child's supplementary groups
+    /// (as set by `initgroups`), group id, and user id are all switched to
+    /// the user's. The pts is given to the user (owned by the user and the
+    /// `tty` group, with mode 0620), and given back if spawning fails.
+    /// `HOME`, `SHELL`, `USER`, and `LOGNAME` are set for the child, except
+    /// for any which were set or removed via [`env`](Self::env) and related
+    /// methods (before or after calling this). This requires the calling
+    /// process to be privileged, and shouldn't be combined with
+    /// [`uid`](Self::uid) or [`gid`](Self::gid). The credentials are
+    /// switched after the session is set up and resource limits are
+    /// applied, but before file descriptors are mapped and the
+    /// [`pre_exec`](Self::pre_exec) hook runs.
+    pub fn as_user(&mut self, user: impl Into<crate::User>) -> &mut Self {
+        self.setup.as_user(user.into());
+        self
+    }
+
+    /// Controls which session and process group the child is placed in,
+    /// and whether the pty becomes its controlling terminal. Defaults to
+    /// [`SessionMode::NewSession`](crate::SessionMode::NewSession).
+    pub fn session_mode(&mut self, mode: crate::SessionMode) -> &mut Self {
+        self.setup.session_mode(mode);
+        self
+    }
+
+    /// Makes `fd` available in the child as file descriptor number
+    /// `child_fd`, replacing any fd previously mapped to `child_fd`. The
+    /// mapped fd will not be close-on-exec in the child, regardless of the
+    /// flags set on `fd`. Mapping fd 0, 1, or 2 replaces the pty for that
+    /// stream. The mapping is applied in the child after its session has
+    /// been set up (see [`session_mode`](Self::session_mode)), and before
+    /// the [`pre_exec`](Self::pre_exec) hook runs. `fd` is only mapped for
+    /// the next child spawned by this `Command`, and is closed in the parent
+    /// once that child has been spawned (or spawning it fails), so that
+    /// the parent sees end of file on a pipe once the child closes its end.
+    pub fn fd_map(
+        &mut self,
+        child_fd: std::os::fd::RawFd,
+        fd: impl Into<std::os::fd::OwnedFd>,
+    ) -> &mut Self {
+        self.setup.fd_map(child_fd, fd.into());
+        self
+    }
+
+    /// See [`async_process::unix::CommandExt::arg0`]
+    pub fn arg0<S>(&mut self, arg: S) -> &mut Self
+    where
+        S: AsRef<std::ffi::OsStr>,
+    {
+        self.inner.arg0(arg);
+        self
+    }
+}
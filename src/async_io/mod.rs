@@ -0,0 +1,11 @@
+//! `async-io` equivalents for the tokio based `pty_process::Command` and
+//! `pty_process::Pty`
+//!
+//! These use [`async_io`](::async_io) and [`async_process`] rather than
+//! tokio, and so can be used with runtimes such as `smol`. The pty types
+//! implement [`futures_io::AsyncRead`] and [`futures_io::AsyncWrite`].
+
+mod command;
+pub use command::Command;
+mod pty;
+pub use pty::{OwnedReadPty, OwnedWritePty, Pts, Pty, ReadPty, WritePty};
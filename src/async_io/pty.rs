@@ -0,0 +1,593 @@
+#![allow(clippy::module_name_repetitions)]
+
+use std::io::{Read as _, Write as _};
+
+type AsyncPty = ::async_io::Async<crate::sys::Pty>;
+
+/// An allocated pty
+pub struct Pty(AsyncPty);
+
+impl Pty {
+    /// Allocate and return a new pty.
+    ///
+    /// # Errors
+    /// Returns an error if the pty failed to be allocated, or if we were
+    /// unable to put it into non-blocking mode.
+    pub fn new() -> crate::Result<Self> {
+        let pty = crate::sys::Pty::open()?;
+        pty.set_nonblocking(true)?;
+        Ok(Self(::async_io::Async::new_nonblocking(pty)?))
+    }
+
+    /// Change the terminal size associated with the pty.
+    ///
+    /// # Errors
+    /// Returns an error if we were unable to set the terminal size.
+    pub fn resize(&self, size: crate::Size) -> crate::Result<()> {
+        self.0.get_ref().set_term_size(size)
+    }
+
+    /// Controls whether closing the write side of the pty (via
+    /// [`futures_io::AsyncWrite::poll_close`]) sends the `VEOF` character
+    /// (usually `^D`) to the child, so that a process reading from the pty
+    /// sees end of file. If the last data written did not end a line, an
+    /// additional `VEOF` is sent first to submit the partial line. This only
+    /// has an effect while the pty is in canonical mode. Disabled by
+    /// default, in which case closing does nothing.
+    pub fn set_eof_on_shutdown(&self, enabled: bool) {
+        self.0.get_ref().set_eof_on_shutdown(enabled);
+    }
+
+    /// Opens a file descriptor for the other end of the pty, which should be
+    /// attached to the child process running in it. See
+    /// [`Command::spawn`](crate::async_io::Command::spawn).
+    ///
+    /// # Errors
+    /// Returns an error if the device node to open could not be determined,
+    /// or if the device node could not be opened.
+    pub fn pts(&self) -> crate::Result<Pts> {
+        Ok(Pts(self.0.get_ref().pts()?))
+    }
+
+    /// Controls whether reading from the pty after every process holding the
+    /// other end of the pty has closed it reports end of file. See
+    /// [`blocking::Pty::set_hangup_as_eof`](crate::blocking::Pty::set_hangup_as_eof).
+    pub fn set_hangup_as_eof(&self, enabled: bool) {
+        self.0.get_ref().set_hangup_as_eof(enabled);
+    }
+
+    /// Returns true if a read from the pty has observed that every process
+    /// holding the other end of the pty has closed it. See
+    /// [`blocking::Pty::is_hung_up`](crate::blocking::Pty::is_hung_up).
+    #[must_use]
+    pub fn is_hung_up(&self) -> bool {
+        self.0.get_ref().is_hung_up()
+    }
+
+    /// Returns a future which resolves once every process holding the other
+    /// end of the pty has closed it, without reading any data from the pty.
+    /// The returned future does not borrow the pty, so it can be awaited
+    /// independently of any reads or writes, including after calling
+    /// [`into_split`](Self::into_split). Note that the [`Pts`] returned by
+    /// [`pts`](Self::pts) also holds the other end of the pty open, so it
+    /// should be dropped once the child has been spawned.
+    ///
+    /// `async-io` can only wait for the pty to become readable, which
+    /// doesn't distinguish a hangup from pending data, so the returned
+    /// future waits on a thread from the [`blocking`](::blocking) thread
+    /// pool.
+    ///
+    /// # Errors
+    /// The returned future resolves to an error if we were unable to
+    /// duplicate the pty file descriptor or to poll it.
+    pub fn hangup(
+        &self,
+    ) -> impl std::future::Future<Output = crate::Result<()>> + Send + 'static
+    {
+        let fds = std::os::fd::AsFd::as_fd(self.0.get_ref())
+            .try_clone_to_owned()
+            .and_then(|pt| Ok((pt, rustix::pipe::pipe()?)));
+        async move {
+            // the write end of the pipe is only closed when this future
+            // completes or is dropped, which wakes up the thread
+            let (pt, (cancel, _cancel)) = fds?;
+            ::blocking::unblock(move || wait_hangup(&pt, &cancel)).await?;
+            Ok(())
+        }
+    }
+
+    /// Splits a `Pty` into a read half and a write half, which can be used to
+    /// read from and write to the pty concurrently. Does not allocate, but
+    /// the returned halves cannot be moved to independent tasks.
+    pub fn split(&mut self) -> (ReadPty<'_>, WritePty<'_>) {
+        (ReadPty(&self.0), WritePty(&self.0))
+    }
+
+    /// Splits a `Pty` into a read half and a write half, which can be used to
+    /// read from and write to the pty concurrently. This method requires an
+    /// allocation, but the returned halves can be moved to independent tasks.
+    /// The original `Pty` instance can be recovered via the
+    /// [`OwnedReadPty::unsplit`] method.
+    #[must_use]
+    pub fn into_split(self) -> (OwnedReadPty, OwnedWritePty) {
+        let Self(pt) = self;
+        let read_pt = std::sync::Arc::new(pt);
+        let write_pt = std::sync::Arc::clone(&read_pt);
+        (OwnedReadPty(read_pt), OwnedWritePty(write_pt))
+    }
+
+    /// Attaches a [`Tap`](crate::Tap), which will observe all data read from
+    /// and written to the pty, through this handle or any other handle to
+    /// the same pty (such as the halves returned by splitting it). Returns
+    /// an id which can be passed to [`remove_tap`](Self::remove_tap).
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.0.get_ref().add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) which was previously attached to the
+    /// pty through any handle. Returns false if the tap was not attached.
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.0.get_ref().remove_tap(id)
+    }
+
+    /// Starts collecting metrics for the pty, and returns a handle for
+    /// querying them. See
+    /// [`blocking::Pty::enable_metrics`](crate::blocking::Pty::enable_metrics).
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.0.get_ref().enable_metrics()
+    }
+}
+
+impl TryFrom<Pty> for std::os::fd::OwnedFd {
+    type Error = crate::Error;
+
+    fn try_from(pty: Pty) -> crate::Result<Self> {
+        Ok(pty.0.into_inner()?.into())
+    }
+}
+
+impl std::os::fd::AsFd for Pty {
+    fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        self.0.get_ref().as_fd()
+    }
+}
+
+impl std::os::fd::AsRawFd for Pty {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.0.get_ref().as_raw_fd()
+    }
+}
+
+impl futures_io::AsyncRead for Pty {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        poll_read(&self.0, cx, buf)
+    }
+}
+
+impl futures_io::AsyncWrite for Pty {
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        poll_write(&self.0, cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        poll_write_vectored(&self.0, cx, bufs)
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        poll_flush(&self.0, cx)
+    }
+
+    fn poll_close(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        poll_close(&self.0, cx)
+    }
+}
+
+/// The child end of the pty
+///
+/// See [`Pty::pts`] and
+/// [`Command::spawn`](crate::async_io::Command::spawn)
+pub struct Pts(pub(crate) crate::sys::Pts);
+
+/// Borrowed read half of a [`Pty`]
+pub struct ReadPty<'a>(&'a AsyncPty);
+
+impl ReadPty<'_> {
+    /// Controls whether reading from the pty after every process holding the
+    /// other end of the pty has closed it reports end of file. See
+    /// [`Pty::set_hangup_as_eof`].
+    pub fn set_hangup_as_eof(&self, enabled: bool) {
+        self.0.get_ref().set_hangup_as_eof(enabled);
+    }
+
+    /// Returns true if a read from the pty has observed that every process
+    /// holding the other end of the pty has closed it. See
+    /// [`Pty::is_hung_up`].
+    #[must_use]
+    pub fn is_hung_up(&self) -> bool {
+        self.0.get_ref().is_hung_up()
+    }
+
+    /// Attaches a [`Tap`](crate::Tap) to the pty. See [`Pty::add_tap`].
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.0.get_ref().add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) from the pty. See
+    /// [`Pty::remove_tap`].
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.0.get_ref().remove_tap(id)
+    }
+
+    /// Starts collecting metrics for the pty, and returns a handle for
+    /// querying them. See [`Pty::enable_metrics`].
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.0.get_ref().enable_metrics()
+    }
+}
+
+impl futures_io::AsyncRead for ReadPty<'_> {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        poll_read(self.0, cx, buf)
+    }
+}
+
+/// Borrowed write half of a [`Pty`]
+pub struct WritePty<'a>(&'a AsyncPty);
+
+impl WritePty<'_> {
+    /// Change the terminal size associated with the pty.
+    ///
+    /// # Errors
+    /// Returns an error if we were unable to set the terminal size.
+    pub fn resize(&self, size: crate::Size) -> crate::Result<()> {
+        self.0.get_ref().set_term_size(size)
+    }
+
+    /// Controls whether closing the write half sends the `VEOF` character to
+    /// the child. See [`Pty::set_eof_on_shutdown`].
+    pub fn set_eof_on_shutdown(&self, enabled: bool) {
+        self.0.get_ref().set_eof_on_shutdown(enabled);
+    }
+
+    /// Attaches a [`Tap`](crate::Tap) to the pty. See [`Pty::add_tap`].
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.0.get_ref().add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) from the pty. See
+    /// [`Pty::remove_tap`].
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.0.get_ref().remove_tap(id)
+    }
+
+    /// Starts collecting metrics for the pty, and returns a handle for
+    /// querying them. See [`Pty::enable_metrics`].
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.0.get_ref().enable_metrics()
+    }
+}
+
+impl futures_io::AsyncWrite for WritePty<'_> {
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        poll_write(self.0, cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        poll_write_vectored(self.0, cx, bufs)
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        poll_flush(self.0, cx)
+    }
+
+    fn poll_close(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        poll_close(self.0, cx)
+    }
+}
+
+/// Owned read half of a [`Pty`]
+#[derive(Debug)]
+pub struct OwnedReadPty(std::sync::Arc<AsyncPty>);
+
+impl OwnedReadPty {
+    /// Attempt to join the two halves of a `Pty` back into a single instance.
+    /// The two halves must have originated from calling
+    /// [`into_split`](Pty::into_split) on a single instance.
+    ///
+    /// # Errors
+    /// Returns an error if the two halves came from different [`Pty`]
+    /// instances. The mismatched halves are returned as part of the error.
+    pub fn unsplit(
+        self,
+        write_half: OwnedWritePty,
+    ) -> Result<Pty, crate::UnsplitError<Self, OwnedWritePty>> {
+        let Self(read_pt) = self;
+        let OwnedWritePty(write_pt) = write_half;
+        if std::sync::Arc::ptr_eq(&read_pt, &write_pt) {
+            drop(write_pt);
+            Ok(Pty(std::sync::Arc::try_unwrap(read_pt)
+                // it shouldn't be possible for more than two references to
+                // the same pty to exist
+                .unwrap_or_else(|_| unreachable!())))
+        } else {
+            Err(crate::UnsplitError(Self(read_pt), OwnedWritePty(write_pt)))
+        }
+    }
+
+    /// Controls whether reading from the pty after every process holding the
+    /// other end of the pty has closed it reports end of file. See
+    /// [`Pty::set_hangup_as_eof`].
+    pub fn set_hangup_as_eof(&self, enabled: bool) {
+        self.0.get_ref().set_hangup_as_eof(enabled);
+    }
+
+    /// Returns true if a read from the pty has observed that every process
+    /// holding the other end of the pty has closed it. See
+    /// [`Pty::is_hung_up`].
+    #[must_use]
+    pub fn is_hung_up(&self) -> bool {
+        self.0.get_ref().is_hung_up()
+    }
+
+    /// Attaches a [`Tap`](crate::Tap) to the pty. See [`Pty::add_tap`].
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.0.get_ref().add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) from the pty. See
+    /// [`Pty::remove_tap`].
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.0.get_ref().remove_tap(id)
+    }
+
+    /// Starts collecting metrics for the pty, and returns a handle for
+    /// querying them. See [`Pty::enable_metrics`].
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.0.get_ref().enable_metrics()
+    }
+}
+
+impl futures_io::AsyncRead for OwnedReadPty {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        poll_read(&self.0, cx, buf)
+    }
+}
+
+/// Owned write half of a [`Pty`]
+#[derive(Debug)]
+pub struct OwnedWritePty(std::sync::Arc<AsyncPty>);
+
+impl OwnedWritePty {
+    /// Change the terminal size associated with the pty.
+    ///
+    /// # Errors
+    /// Returns an error if we were unable to set the terminal size.
+    pub fn resize(&self, size: crate::Size) -> crate::Result<()> {
+        self.0.get_ref().set_term_size(size)
+    }
+
+    /// Controls whether closing the write half sends the `VEOF` character to
+    /// the child. See [`Pty::set_eof_on_shutdown`].
+    pub fn set_eof_on_shutdown(&self, enabled: bool) {
+        self.0.get_ref().set_eof_on_shutdown(enabled);
+    }
+
+    /// Attaches a [`Tap`](crate::Tap) to the pty. See [`Pty::add_tap`].
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.0.get_ref().add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) from the pty. See
+    /// [`Pty::remove_tap`].
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.0.get_ref().remove_tap(id)
+    }
+
+    /// Starts collecting metrics for the pty, and returns a handle for
+    /// querying them. See [`Pty::enable_metrics`].
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.0.get_ref().enable_metrics()
+    }
+}
+
+impl futures_io::AsyncWrite for OwnedWritePty {
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        poll_write(&self.0, cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        poll_write_vectored(&self.0, cx, bufs)
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        poll_flush(&self.0, cx)
+    }
+
+    fn poll_close(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        poll_close(&self.0, cx)
+    }
+}
+
+fn poll_read(
+    pty: &AsyncPty,
+    cx: &mut std::task::Context<'_>,
+    buf: &mut [u8],
+) -> std::task::Poll<std::io::Result<usize>> {
+    loop {
+        match pty.get_ref().read(buf) {
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+            res => return std::task::Poll::Ready(res),
+        }
+        match pty.poll_readable(cx) {
+            std::task::Poll::Ready(res) => res?,
+            std::task::Poll::Pending => return std::task::Poll::Pending,
+        }
+    }
+}
+
+fn poll_write(
+    pty: &AsyncPty,
+    cx: &mut std::task::Context<'_>,
+    buf: &[u8],
+) -> std::task::Poll<std::io::Result<usize>> {
+    loop {
+        match pty.get_ref().write(buf) {
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+            res => return std::task::Poll::Ready(res),
+        }
+        match pty.poll_writable(cx) {
+            std::task::Poll::Ready(res) => res?,
+            std::task::Poll::Pending => return std::task::Poll::Pending,
+        }
+    }
+}
+
+fn poll_write_vectored(
+    pty: &AsyncPty,
+    cx: &mut std::task::Context<'_>,
+    bufs: &[std::io::IoSlice<'_>],
+) -> std::task::Poll<std::io::Result<usize>> {
+    loop {
+        match pty.get_ref().write_vectored(bufs) {
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+            res => return std::task::Poll::Ready(res),
+        }
+        match pty.poll_writable(cx) {
+            std::task::Poll::Ready(res) => res?,
+            std::task::Poll::Pending => return std::task::Poll::Pending,
+        }
+    }
+}
+
+fn poll_flush(
+    pty: &AsyncPty,
+    cx: &mut std::task::Context<'_>,
+) -> std::task::Poll<std::io::Result<()>> {
+    loop {
+        match pty.get_ref().flush() {
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+            res => return std::task::Poll::Ready(res),
+        }
+        match pty.poll_writable(cx) {
+            std::task::Poll::Ready(res) => res?,
+            std::task::Poll::Pending => return std::task::Poll::Pending,
+        }
+    }
+}
+
+fn poll_close(
+    pty: &AsyncPty,
+    cx: &mut std::task::Context<'_>,
+) -> std::task::Poll<std::io::Result<()>> {
+    if !pty.get_ref().eof_on_shutdown() {
+        return std::task::Poll::Ready(Ok(()));
+    }
+    loop {
+        match pty.get_ref().send_eof() {
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+            res => return std::task::Poll::Ready(res),
+        }
+        match pty.poll_writable(cx) {
+            std::task::Poll::Ready(res) => res?,
+            std::task::Poll::Pending => return std::task::Poll::Pending,
+        }
+    }
+}
+
+// waits for the pty to hang up, or for cancel (the read end of a pipe) to
+// see end of file
+fn wait_hangup(
+    pt: &std::os::fd::OwnedFd,
+    cancel: &std::os::fd::OwnedFd,
+) -> std::io::Result<()> {
+    let mut fds = [
+        rustix::event::PollFd::new(pt, rustix::event::PollFlags::empty()),
+        rustix::event::PollFd::new(cancel, rustix::event::PollFlags::IN),
+    ];
+    loop {
+        match rustix::event::poll(&mut fds, -1) {
+            Ok(_) => return Ok(()),
+            Err(rustix::io::Errno::INTR) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
@@ -6,25 +6,47 @@ pub struct Command {
     stdin: bool,
     stdout: bool,
     stderr: bool,
-    pre_exec_set: bool,
-    pre_exec: Option<
-        Box<dyn FnMut() -> std::io::Result<()> + Send + Sync + 'static>,
-    >,
+    setup: crate::child::Setup,
 }
 
 impl Command {
     /// See [`std::process::Command::new`]
     pub fn new<S: AsRef<std::ffi::OsStr>>(program: S) -> Self {
+        let mut inner = std::process::Command::new(program);
+        let setup = crate::child::Setup::new(&mut inner);
         Self {
-            inner: std::process::Command::new(program),
+            inner,
             stdin: false,
             stdout: false,
             stderr: false,
-            pre_exec_set: false,
-            pre_exec: None,
+            setup,
         }
     }
 
+    /// Creates a command which runs the login shell of `user`, as `login`
+    /// does. The shell is run with an `argv[0]` of its name prefixed with
+    /// `-` (such as `-bash`), in the user's home directory, and with `TERM`
+    /// set to `xterm-256color`. `COLUMNS` and `LINES` are set from the size
+    /// of the pty each time it is spawned. If `user` isn't the current user,
+    /// the shell is run as that user via [`as_user`](Self::as_user), and
+    /// otherwise the same login environment variables are set directly.
+    /// Any of these variables can be overridden via [`env`](Self::env) and
+    /// related methods.
+    ///
+    /// # Errors
+    /// Returns an error if the user can't be looked up.
+    pub fn login_shell(user: impl Into<crate::User>) -> crate::Result<Self> {
+        let shell = crate::login::Shell::new(user.into())?;
+        let mut cmd = Self::new(&shell.program);
+        cmd.arg0(&shell.arg0).current_dir(&shell.dir);
+        if let Some(user) = shell.user {
+            cmd.as_user(user);
+        }
+        cmd.setup.default_env(shell.env);
+        cmd.setup.size_env();
+        Ok(cmd)
+    }
+
     /// See [`std::process::Command::arg`]
     pub fn arg<S: AsRef<std::ffi::OsStr>>(&mut self, arg: S) -> &mut Self {
         self.inner.arg(arg);
@@ -47,6 +69,7 @@ impl Command {
         K: AsRef<std::ffi::OsStr>,
         V: AsRef<std::ffi::OsStr>,
     {
+        self.setup.caller_env(key.as_ref());
         self.inner.env(key, val);
         self
     }
@@ -58,7 +81,9 @@ impl Command {
         K: AsRef<std::ffi::OsStr>,
         V: AsRef<std::ffi::OsStr>,
     {
-        self.inner.envs(vars);
+        for (key, val) in vars {
+            self.env(key, val);
+        }
         self
     }
 
@@ -67,12 +92,14 @@ impl Command {
         &mut self,
         key: K,
     ) -> &mut Self {
+        self.setup.caller_env(key.as_ref());
         self.inner.env_remove(key);
         self
     }
 
     /// See [`std::process::Command::env_clear`]
     pub fn env_clear(&mut self) -> &mut Self {
+        self.setup.caller_env_clear();
         self.inner.env_clear();
         self
     }
@@ -123,7 +150,8 @@ impl Command {
     /// to [`stdin`](Self::stdin), [`stdout`](Self::stdout), or
     /// [`stderr`](Self::stderr). The newly created child process will also be
     /// made the session leader of a new session, and will have the given
-    /// pty set as its controlling terminal.
+    /// pty set as its controlling terminal (this can be changed via
+    /// [`session_mode`](Self::session_mode)).
     ///
     /// # Errors
     /// Returns an error if we fail to allocate new file descriptors for
@@ -135,35 +163,50 @@ impl Command {
         &mut self,
         pts: &crate::blocking::Pts,
     ) -> crate::Result<std::process::Child> {
-        let (stdin, stdout, stderr) = pts.0.setup_subprocess()?;
+        self.spawn_piped(pts, None, None)
+    }
+
+    // spawns the child with the given pipe ends attached to its stdin and
+    // stdout in place of the pty (unless those were overridden). the pipe
+    // ends aren't kept open by the command afterwards, so that the other
+    // end of each pipe sees end of file once the child closes it.
+    pub(crate) fn spawn_piped(
+        &mut self,
+        pts: &crate::blocking::Pts,
+        stdin: Option<std::os::fd::OwnedFd>,
+        stdout: Option<std::os::fd::OwnedFd>,
+    ) -> crate::Result<std::process::Child> {
+        self.inner.envs(self.setup.prepare_env(&pts.0)?);
+        let (pty_stdin, pty_stdout, stderr) = pts.0.setup_subprocess()?;
+        let stdin_piped = !self.stdin && stdin.is_some();
+        let stdout_piped = !self.stdout && stdout.is_some();
 
         if !self.stdin {
-            self.inner.stdin(stdin);
+            self.inner.stdin(stdin.map_or(pty_stdin, Into::into));
         }
         if !self.stdout {
-            self.inner.stdout(stdout);
+            self.inner.stdout(stdout.map_or(pty_stdout, Into::into));
         }
         if !self.stderr {
             self.inner.stderr(stderr);
         }
 
-        let mut session_leader = pts.0.session_leader();
-        // Safety: setsid() is an async-signal-safe function and ioctl() is a
-        // raw syscall (which is inherently async-signal-safe).
-        if let Some(mut custom) = self.pre_exec.take() {
-            unsafe {
-                self.inner.pre_exec(move || {
-                    session_leader()?;
-                    custom()?;
-                    Ok(())
-                })
-            };
-        } else if !self.pre_exec_set {
-            unsafe { self.inner.pre_exec(session_leader) };
+        let res = self.setup.spawn(
+            &pts.0,
+            || self.inner.spawn(),
+            |child| Some(child.id()),
+        );
+        if stdin_piped {
+            self.inner.stdin(std::process::Stdio::null());
         }
-        self.pre_exec_set = true;
+        if stdout_piped {
+            self.inner.stdout(std::process::Stdio::null());
+        }
+        res
+    }
 
-        Ok(self.inner.spawn()?)
+    pub(crate) fn set_job(&self, job: Option<crate::child::Job>) {
+        self.setup.job(job);
     }
 
     /// See [`std::os::unix::process::CommandExt::uid`]
@@ -184,7 +227,106 @@ impl Command {
     where
         F: FnMut() -> std::io::Result<()> + Send + Sync + 'static,
     {
-        self.pre_exec = Some(Box::new(f));
+        self.setup.pre_exec(Box::new(f));
+        self
+    }
+
+    /// Makes the child be sent `signal` when the thread which spawned it
+    /// exits (see `PR_SET_PDEATHSIG` in `prctl(2)`), so that it doesn't
+    /// outlive the calling process. If the calling process has already
+    /// exited by the time the child is set up, the signal is sent to the
+    /// child immediately. Note that this is tied to the spawning thread
+    /// rather than the whole process, so it should only be used when
+    /// spawning from a thread which lives as long as the process. (Linux
+    /// only)
+    #[cfg(target_os = "linux")]
+    pub fn kill_on_parent_death(&mut self, signal: i32) -> &mut Self {
+        self.setup.kill_on_parent_death(signal);
+        self
+    }
+
+    /// Sets the soft and hard limits for `resource` in the child via
+    /// `setrlimit`, replacing any limits previously set for `resource`.
+    /// `None` means that the resource is unlimited. Note that an
+    /// unprivileged process can't raise its hard limits, so both limits
+    /// should usually be given.
+    pub fn rlimit(
+        &mut self,
+        resource: crate::Resource,
+        soft: Option<u64>,
+        hard: Option<u64>,
+    ) -> &mut Self {
+        self.setup.rlimit(resource, soft, hard);
+        self
+    }
+
+    /// Sets the file mode creation mask of the child via `umask`.
+    pub fn umask(&mut self, mask: u32) -> &mut Self {
+        self.setup.umask(mask);
+        self
+    }
+
+    /// Adds `increment` to the niceness of the child via `nice` (so higher
+    /// values give it a lower scheduling priority). Note that an
+    /// unprivileged process can't decrease its niceness.
+    pub fn nice(&mut self, increment: i32) -> &mut Self {
+        self.setup.nice(increment);
+        self
+    }
+
+    /// Sets the I/O scheduling class and priority of the child via
+    /// `ioprio_set`. (Linux only)
+    ///
+    /// # Panics
+    /// Panics if the priority level is higher than 7.
+    #[cfg(target_os = "linux")]
+    pub fn io_priority(&mut self, priority: crate::IoPriority) -> &mut Self {
+        self.setup.io_priority(priority);
+        self
+    }
+
+    /// Runs the child as `user`, as `login` does. The user's passwd entry
+    /// is looked up when spawning, and the child's supplementary groups
+    /// (as set by `initgroups`), group id, and user id are all switched to
+    /// the user's. The pts is given to the user (owned by the user and the
+    /// `tty` group, with mode 0620), and given back if spawning fails.
+    /// `HOME`, `SHELL`, `USER`, and `LOGNAME` are set for the child, except
+    /// for any which were set or removed via [`env`](Self::env) and related
+    /// methods (before or after calling this). This requires the calling
+    /// process to be privileged, and shouldn't be combined with
+    /// [`uid`](Self::uid) or [`gid`](Self::gid). The credentials are
+    /// switched after the session is set up and resource limits are
+    /// applied, but before file descriptors are mapped and the
+    /// [`pre_exec`](Self::pre_exec) hook runs.
+    pub fn as_user(&mut self, user: impl Into<crate::User>) -> &mut Self {
+        self.setup.as_user(user.into());
+        self
+    }
+
+    /// Controls which session and process group the child is placed in,
+    /// and whether the pty becomes its controlling terminal. Defaults to
+    /// [`SessionMode::NewSession`](crate::SessionMode::NewSession).
+    pub fn session_mode(&mut self, mode: crate::SessionMode) -> &mut Self {
+        self.setup.session_mode(mode);
+        self
+    }
+
+    /// Makes `fd` available in the child as file descriptor number
+    /// `child_fd`, replacing any fd previously mapped to `child_fd`. The
+    /// mapped fd will not be close-on-exec in the child, regardless of the
+    /// flags set on `fd`. Mapping fd 0, 1, or 2 replaces the pty for that
+    /// stream. The mapping is applied in the child after its session has
+    /// been set up (see [`session_mode`](Self::session_mode)), and before
+    /// the [`pre_exec`](Self::pre_exec) hook runs. `fd` is only mapped for
+    /// the next child spawned by this `Command`, and is closed in the parent
+    /// once that child has been spawned (or spawning it fails), so that
+    /// the parent sees end of file on a pipe once the child closes its end.
+    pub fn fd_map(
+        &mut self,
+        child_fd: std::os::fd::RawFd,
+        fd: impl Into<std::os::fd::OwnedFd>,
+    ) -> &mut Self {
+        self.setup.fd_map(child_fd, fd.into());
         self
     }
 
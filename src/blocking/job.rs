@@ -0,0 +1,264 @@
+/// Job control for running several jobs on a single pty, as a shell does
+///
+/// The calling process becomes the leader of a new session which has the
+/// pty as its controlling terminal, and each job is spawned into its own
+/// process group within that session. Only one job at a time can be in the
+/// foreground (able to read from the pty and receive signals generated by
+/// it, such as `SIGINT` from `^C` or `SIGTSTP` from `^Z`), and jobs which
+/// try to read from the pty while in the background are stopped with
+/// `SIGTTIN`.
+///
+/// Note that this changes the session and controlling terminal of the
+/// entire calling process (see [`new`](Self::new)), so it is mainly useful
+/// in a process which is dedicated to running jobs on a single pty, such as
+/// a shell or a helper process forked for that purpose.
+pub struct JobControl {
+    pts: crate::blocking::Pts,
+    pgid: rustix::process::Pid,
+}
+
+impl JobControl {
+    /// Makes `pts` the controlling terminal of the calling process, first
+    /// making the calling process the leader of a new session if it isn't
+    /// already a session leader.
+    ///
+    /// This affects the entire calling process, not just the returned
+    /// value, and can't be undone:
+    /// * the calling process (and every thread in it) is moved into a new
+    ///   session and process group, and is detached from its current
+    ///   controlling terminal (if any)
+    /// * it is sent `SIGHUP` when the pty is closed, as with any session
+    ///   leader
+    /// * a process can only have one controlling terminal, so only one
+    ///   `JobControl` can be used per process
+    ///
+    /// # Errors
+    /// Returns an error with [`Operation::Setsid`](crate::Operation::Setsid)
+    /// (`EPERM`) if the calling process is a process group leader but not a
+    /// session leader, since it can't start a new session then. This is the
+    /// case when it was run as a job from an interactive shell, for
+    /// instance. Returns an error with
+    /// [`Operation::SetControllingTerminal`](crate::Operation::SetControllingTerminal)
+    /// if the pty can't be made the controlling terminal of the calling
+    /// process (such as when the calling process was already a session
+    /// leader with a different controlling terminal, or the pty is already
+    /// the controlling terminal of another session).
+    pub fn new(pts: crate::blocking::Pts) -> crate::Result<Self> {
+        let pgid = crate::child::take_controlling_terminal(&pts.0)?;
+        Ok(Self { pts, pgid })
+    }
+
+    /// Spawns `pipeline` on the pty as a single job, in a new process group
+    /// (see [`Pipeline::spawn`](crate::blocking::Pipeline::spawn)). If
+    /// `foreground` is true, the job will be made the foreground job of the
+    /// pty. If any of the commands fails to spawn, the processes which were
+    /// already spawned for the job are killed. A single command can be run
+    /// as a job by spawning a pipeline containing only that command.
+    ///
+    /// # Errors
+    /// Returns an error if `pipeline` is empty, if any of the commands fails
+    /// to spawn (see [`Command::spawn`](crate::blocking::Command::spawn)),
+    /// or if the job couldn't be made the foreground job.
+    pub fn spawn(
+        &self,
+        pipeline: &mut crate::blocking::Pipeline,
+        foreground: bool,
+    ) -> crate::Result<Job> {
+        let children = pipeline.spawn_job(&self.pts, foreground)?;
+        let job = Job::new(
+            children
+                .iter()
+                .map(rustix::process::Pid::from_child)
+                .collect(),
+            foreground,
+        );
+        if foreground {
+            // the children do this too, but they may not have run yet
+            crate::child::set_job_foreground(&self.pts.0, job.pgid)?;
+        }
+        Ok(job)
+    }
+
+    /// Waits until every process in `job` has exited, or until any process
+    /// in `job` is stopped. If `job` was in the foreground, the calling
+    /// process is made the foreground process group again afterwards (as
+    /// a shell does when a job exits or is suspended).
+    ///
+    /// # Errors
+    /// Returns an error if waiting for the processes fails, or if the
+    /// calling process couldn't be made the foreground process group again.
+    pub fn wait(&self, job: &mut Job) -> crate::Result<JobStatus> {
+        let res = job.wait_for_change();
+        self.take_foreground(job)?;
+        res
+    }
+
+    /// Makes `job` the foreground job of the pty, and continues it if it
+    /// was stopped.
+    ///
+    /// # Errors
+    /// Returns an error if the job could not be made the foreground job, or
+    /// if sending `SIGCONT` to it fails.
+    pub fn foreground(&self, job: &mut Job) -> crate::Result<()> {
+        crate::child::set_job_foreground(&self.pts.0, job.pgid)?;
+        job.foreground = true;
+        Ok(rustix::process::kill_process_group(
+            job.pgid,
+            rustix::process::Signal::Cont,
+        )?)
+    }
+
+    /// Continues `job` in the background, if it was stopped. If it was the
+    /// foreground job, the calling process is made the foreground process
+    /// group instead.
+    ///
+    /// # Errors
+    /// Returns an error if the calling process could not be made the
+    /// foreground process group, or if sending `SIGCONT` to the job fails.
+    pub fn background(&self, job: &mut Job) -> crate::Result<()> {
+        self.take_foreground(job)?;
+        Ok(rustix::process::kill_process_group(
+            job.pgid,
+            rustix::process::Signal::Cont,
+        )?)
+    }
+
+    /// Returns the process group id of the current foreground process group
+    /// of the pty.
+    ///
+    /// # Errors
+    /// Returns an error if the foreground process group can't be retrieved.
+    pub fn foreground_pgid(&self) -> crate::Result<u32> {
+        Ok(rustix::termios::tcgetpgrp(&self.pts.0)?
+            .as_raw_nonzero()
+            .get()
+            .unsigned_abs())
+    }
+
+    fn take_foreground(&self, job: &mut Job) -> crate::Result<()> {
+        if job.foreground {
+            crate::child::set_job_foreground(&self.pts.0, self.pgid)?;
+            job.foreground = false;
+        }
+        Ok(())
+    }
+}
+
+/// A job spawned by [`JobControl::spawn`]
+#[derive(Debug)]
+pub struct Job {
+    pub(crate) pgid: rustix::process::Pid,
+    pids: Vec<rustix::process::Pid>,
+    statuses: Vec<Option<std::process::ExitStatus>>,
+    pub(crate) foreground: bool,
+}
+
+impl Job {
+    pub(crate) fn new(
+        pids: Vec<rustix::process::Pid>,
+        foreground: bool,
+    ) -> Self {
+        Self {
+            pgid: pids[0],
+            statuses: vec![None; pids.len()],
+            pids,
+            foreground,
+        }
+    }
+
+    /// Returns the process group id of the job.
+    #[must_use]
+    pub fn pgid(&self) -> u32 {
+        self.pgid.as_raw_nonzero().get().unsigned_abs()
+    }
+
+    /// Returns the process ids of the processes in the job, in the order
+    /// they were spawned.
+    pub fn pids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.pids
+            .iter()
+            .map(|pid| pid.as_raw_nonzero().get().unsigned_abs())
+    }
+
+    /// Returns true if the job is the foreground job of the pty.
+    #[must_use]
+    pub fn is_foreground(&self) -> bool {
+        self.foreground
+    }
+
+    pub(crate) fn wait_for_change(&mut self) -> crate::Result<JobStatus> {
+        loop {
+            if let Some(status) = self.reap(true)? {
+                return Ok(status);
+            }
+        }
+    }
+
+    // like wait_for_change, but returns None instead of blocking once no
+    // more processes in the job have changed state
+    #[cfg(feature = "async")]
+    pub(crate) fn try_wait_for_change(
+        &mut self,
+    ) -> crate::Result<Option<JobStatus>> {
+        self.reap(false)
+    }
+
+    // reaps processes in the job until every one of them has exited or one
+    // of them is stopped
+    fn reap(&mut self, block: bool) -> crate::Result<Option<JobStatus>> {
+        use std::os::unix::process::ExitStatusExt as _;
+
+        let options = if block {
+            libc::WUNTRACED
+        } else {
+            libc::WUNTRACED | libc::WNOHANG
+        };
+        while self.statuses.iter().any(Option::is_none) {
+            let mut status = 0;
+            // rustix's waitpgid doesn't return which process changed state
+            let pid = unsafe {
+                libc::waitpid(
+                    -self.pgid.as_raw_nonzero().get(),
+                    &raw mut status,
+                    options,
+                )
+            };
+            if pid == -1 {
+                let e = std::io::Error::last_os_error();
+                if e.kind() == std::io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(e.into());
+            }
+            if pid == 0 {
+                return Ok(None);
+            }
+            let status = std::process::ExitStatus::from_raw(status);
+            if let Some(signal) = status.stopped_signal() {
+                return Ok(Some(JobStatus::Stopped(signal)));
+            }
+            if let Some(idx) = self
+                .pids
+                .iter()
+                .position(|job_pid| job_pid.as_raw_nonzero().get() == pid)
+            {
+                self.statuses[idx] = Some(status);
+            }
+        }
+        Ok(Some(JobStatus::Exited(
+            self.statuses.iter().flatten().copied().collect(),
+        )))
+    }
+}
+
+/// The result of [`JobControl::wait`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    /// A process in the job was stopped by the given signal (such as
+    /// `SIGTSTP` or `SIGTTIN`). The job can be continued via
+    /// [`JobControl::foreground`] or [`JobControl::background`].
+    Stopped(i32),
+    /// Every process in the job has exited, with the given statuses (in the
+    /// order the processes were spawned).
+    Exited(Vec<std::process::ExitStatus>),
+}
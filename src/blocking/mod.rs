@@ -3,5 +3,17 @@
 
 mod command;
 pub use command::Command;
+mod job;
+pub use job::{Job, JobControl, JobStatus};
+#[cfg(target_os = "linux")]
+mod pidfd;
+#[cfg(target_os = "linux")]
+pub use pidfd::Pidfd;
+mod pipeline;
+pub use pipeline::Pipeline;
 mod pty;
-pub use pty::{Pts, Pty};
+pub use pty::{OwnedReadPty, OwnedWritePty, Pts, Pty};
+#[cfg(target_os = "linux")]
+mod supervisor;
+#[cfg(target_os = "linux")]
+pub use supervisor::{SessionStatus, Supervisor};
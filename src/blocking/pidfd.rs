@@ -0,0 +1,66 @@
+/// A pidfd for a child process, which can be used to signal and wait for
+/// the child without any risk of the pid having been reused (Linux only)
+pub struct Pidfd(std::os::fd::OwnedFd);
+
+impl Pidfd {
+    /// Opens a pidfd for `child` via `pidfd_open`. The child is consumed,
+    /// since waiting via the pidfd reaps it, after which
+    /// [`std::process::Child::wait`] and [`std::process::Child::kill`] could
+    /// affect an unrelated process which reused its pid. Any of its stdio
+    /// handles which are still attached are closed.
+    ///
+    /// # Errors
+    /// Returns an error if the pidfd can't be opened.
+    pub fn new(child: std::process::Child) -> crate::Result<Self> {
+        let fd = crate::pidfd::open(&child)?;
+        // dropping a std child doesn't wait for it
+        drop(child);
+        Ok(Self(fd))
+    }
+
+    /// Sends `signal` to the child via `pidfd_send_signal`.
+    ///
+    /// # Errors
+    /// Returns an error if `signal` isn't a valid signal, or if sending the
+    /// signal fails.
+    pub fn send_signal(&self, signal: i32) -> crate::Result<()> {
+        crate::pidfd::send_signal(std::os::fd::AsFd::as_fd(&self.0), signal)
+    }
+
+    /// Reaps the child if it has exited, without waiting.
+    ///
+    /// # Errors
+    /// Returns an error if the child has already been reaped.
+    pub fn try_wait(
+        &self,
+    ) -> crate::Result<Option<std::process::ExitStatus>> {
+        crate::pidfd::wait(std::os::fd::AsFd::as_fd(&self.0), false)
+    }
+
+    /// Waits for the child to exit, and reaps it.
+    ///
+    /// # Errors
+    /// Returns an error if the child has already been reaped.
+    pub fn wait(&self) -> crate::Result<std::process::ExitStatus> {
+        // a blocking waitid only returns once the child has exited
+        loop {
+            if let Some(status) =
+                crate::pidfd::wait(std::os::fd::AsFd::as_fd(&self.0), true)?
+            {
+                return Ok(status);
+            }
+        }
+    }
+}
+
+impl std::os::fd::AsFd for Pidfd {
+    fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl From<Pidfd> for std::os::fd::OwnedFd {
+    fn from(pidfd: Pidfd) -> Self {
+        pidfd.0
+    }
+}
@@ -0,0 +1,132 @@
+/// A pipeline of commands attached to a single pty, as a shell runs
+/// `ls | less`
+///
+/// The first command reads from the pty, each command's stdout is connected
+/// to the next command's stdin via a pipe, and the last command writes to
+/// the pty. Every command writes its stderr to the pty. Any of these can be
+/// overridden via [`Command::stdin`](crate::blocking::Command::stdin),
+/// [`Command::stdout`](crate::blocking::Command::stdout), and
+/// [`Command::stderr`](crate::blocking::Command::stderr) as usual.
+#[derive(Default)]
+pub struct Pipeline {
+    cmds: Vec<crate::blocking::Command>,
+}
+
+impl Pipeline {
+    /// Creates an empty pipeline.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `cmd` to the pipeline.
+    pub fn cmd(&mut self, cmd: crate::blocking::Command) -> &mut Self {
+        self.cmds.push(cmd);
+        self
+    }
+
+    /// Spawns every command in the pipeline on the given pty. The children
+    /// are all placed in a single new process group (whose id is the pid of
+    /// the first child) in the session of the calling process, and the
+    /// foreground process group of the pty is left unchanged. To run the
+    /// pipeline in the foreground of a pty which is the controlling terminal
+    /// of the calling process, use
+    /// [`JobControl::spawn`](crate::blocking::JobControl::spawn) instead.
+    /// The session modes of the commands are ignored. If any command fails
+    /// to spawn, the commands which were already spawned are killed.
+    ///
+    /// Returns the children in the order the commands were added.
+    ///
+    /// # Errors
+    /// Returns an error if the pipeline is empty, if creating the pipes
+    /// fails, or if any command fails to spawn (see
+    /// [`Command::spawn`](crate::blocking::Command::spawn)).
+    pub fn spawn(
+        &mut self,
+        pts: &crate::blocking::Pts,
+    ) -> crate::Result<Vec<std::process::Child>> {
+        self.spawn_job(pts, false)
+    }
+
+    // spawns the pipeline, making its process group the foreground process
+    // group of the pty from within the children if foreground is true (see
+    // JobControl::spawn)
+    pub(crate) fn spawn_job(
+        &mut self,
+        pts: &crate::blocking::Pts,
+        foreground: bool,
+    ) -> crate::Result<Vec<std::process::Child>> {
+        if self.cmds.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "no commands given for pipeline",
+            )
+            .into());
+        }
+
+        let mut children = vec![];
+        let mut pgid = None;
+        if let Err(e) =
+            self.spawn_all(pts, foreground, &mut children, &mut pgid)
+        {
+            if let Some(pgid) = pgid {
+                let _ = rustix::process::kill_process_group(
+                    pgid,
+                    rustix::process::Signal::Kill,
+                );
+            }
+            for child in &mut children {
+                let _ = child.wait();
+            }
+            if foreground && pgid.is_some() {
+                let _ = crate::child::set_foreground(
+                    std::os::fd::AsFd::as_fd(&pts.0),
+                    rustix::process::getpgrp(),
+                );
+            }
+            return Err(e);
+        }
+        Ok(children)
+    }
+
+    // spawns the commands in order, adding each child to children as soon
+    // as it is spawned, so that the caller can clean up after a failure
+    fn spawn_all(
+        &mut self,
+        pts: &crate::blocking::Pts,
+        foreground: bool,
+        children: &mut Vec<std::process::Child>,
+        pgid: &mut Option<rustix::process::Pid>,
+    ) -> crate::Result<()> {
+        let last = self.cmds.len() - 1;
+        let mut stdin = None;
+        for (i, cmd) in self.cmds.iter_mut().enumerate() {
+            let (next_stdin, stdout) = if i == last {
+                (None, None)
+            } else {
+                let (read, write) = crate::child::pipe()?;
+                (Some(read), Some(write))
+            };
+            cmd.set_job(Some(crate::child::Job {
+                pgid: *pgid,
+                foreground,
+            }));
+            let res = cmd.spawn_piped(pts, stdin.take(), stdout);
+            cmd.set_job(None);
+            let child = res?;
+            let pid = rustix::process::Pid::from_child(&child);
+            children.push(child);
+            // also set the process group from the parent, so that it is in
+            // place regardless of whether the parent or the child runs
+            // first. this fails if the child has already called exec, but
+            // then it has also already set it.
+            let _ = rustix::process::setpgid(
+                Some(pid),
+                Some(pgid.unwrap_or(pid)),
+            );
+            pgid.get_or_insert(pid);
+            stdin = next_stdin;
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,22 @@
+use std::io::Read as _;
+
 /// An allocated pty
-pub struct Pty(crate::sys::Pty);
+///
+/// The underlying file descriptor is always in non-blocking mode, and
+/// blocking reads and writes (along with their timeouts) are implemented by
+/// polling. Code which uses the file descriptor directly (via [`AsFd`] or
+/// [`AsRawFd`]) must be prepared to handle [`std::io::ErrorKind::WouldBlock`].
+/// Converting the pty into an [`OwnedFd`] puts the file descriptor back into
+/// blocking mode.
+///
+/// [`AsFd`]: std::os::fd::AsFd
+/// [`AsRawFd`]: std::os::fd::AsRawFd
+/// [`OwnedFd`]: std::os::fd::OwnedFd
+pub struct Pty {
+    pt: crate::sys::Pty,
+    read_timeout: Option<std::time::Duration>,
+    write_timeout: Option<std::time::Duration>,
+}
 
 impl Pty {
     /// Allocate and return a new pty.
@@ -7,7 +24,16 @@ impl Pty {
     /// # Errors
     /// Returns an error if the pty failed to be allocated.
     pub fn new() -> crate::Result<Self> {
-        Ok(Self(crate::sys::Pty::open()?))
+        let pt = crate::sys::Pty::open()?;
+        // blocking reads and writes are implemented by polling, so that
+        // they can time out without a read or write blocking after poll
+        // reported readiness (if another thread got to the data first)
+        pt.set_nonblocking(true)?;
+        Ok(Self {
+            pt,
+            read_timeout: None,
+            write_timeout: None,
+        })
     }
 
     /// Change the terminal size associated with the pty.
@@ -15,7 +41,113 @@ impl Pty {
     /// # Errors
     /// Returns an error if we were unable to set the terminal size.
     pub fn resize(&self, size: crate::Size) -> crate::Result<()> {
-        self.0.set_term_size(size)
+        self.pt.set_term_size(size)
+    }
+
+    /// Sets the timeout for reads from the pty. If a read does not complete
+    /// within this duration, it will return an error of kind
+    /// [`std::io::ErrorKind::TimedOut`]. If `None` (the default), reads will
+    /// block indefinitely.
+    pub fn set_read_timeout(&mut self, timeout: Option<std::time::Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    /// Returns the timeout for reads from the pty. See
+    /// [`set_read_timeout`](Self::set_read_timeout).
+    #[must_use]
+    pub fn read_timeout(&self) -> Option<std::time::Duration> {
+        self.read_timeout
+    }
+
+    /// Sets the timeout for writes to the pty. If a write cannot make any
+    /// progress within this duration (for instance, because the child isn't
+    /// reading its input), it will return an error of kind
+    /// [`std::io::ErrorKind::TimedOut`]. If `None` (the default), writes will
+    /// block indefinitely.
+    pub fn set_write_timeout(
+        &mut self,
+        timeout: Option<std::time::Duration>,
+    ) {
+        self.write_timeout = timeout;
+    }
+
+    /// Returns the timeout for writes to the pty. See
+    /// [`set_write_timeout`](Self::set_write_timeout).
+    #[must_use]
+    pub fn write_timeout(&self) -> Option<std::time::Duration> {
+        self.write_timeout
+    }
+
+    /// Puts the pty into non-blocking mode. While enabled, reads and writes
+    /// which would otherwise block return an error of kind
+    /// [`std::io::ErrorKind::WouldBlock`] immediately, and the read and
+    /// write timeouts are ignored. This is required when registering the pty
+    /// with an event loop, such as via the `mio` feature, and can be
+    /// disabled again after deregistering it. This setting is shared with
+    /// the halves returned by [`into_split`](Self::into_split).
+    ///
+    /// Note that the underlying file descriptor is always in non-blocking
+    /// mode, regardless of this setting (blocking reads and writes wait for
+    /// the pty to become ready via `poll`). Converting the pty into an
+    /// [`OwnedFd`](std::os::fd::OwnedFd) puts the file descriptor back into
+    /// blocking mode.
+    pub fn set_nonblocking(&self, nonblocking: bool) {
+        self.pt.set_would_block(nonblocking);
+    }
+
+    /// Returns true if the pty is in non-blocking mode. See
+    /// [`set_nonblocking`](Self::set_nonblocking).
+    #[must_use]
+    pub fn is_nonblocking(&self) -> bool {
+        self.pt.would_block()
+    }
+
+    /// Reads from the pty, failing with an error of kind
+    /// [`std::io::ErrorKind::TimedOut`] if no data is available before
+    /// `deadline`. This ignores the timeout set by
+    /// [`set_read_timeout`](Self::set_read_timeout).
+    ///
+    /// # Errors
+    /// Returns an error if the deadline passes, or if reading from the pty
+    /// fails.
+    pub fn read_with_deadline(
+        &self,
+        buf: &mut [u8],
+        deadline: std::time::Instant,
+    ) -> std::io::Result<usize> {
+        read_until(&self.pt, Some(deadline), |mut pt| pt.read(buf))
+    }
+
+    /// Controls whether reading from the pty after every process holding the
+    /// other end of the pty has closed it reports end of file (a read of
+    /// zero bytes) rather than an error. On Linux, this condition is
+    /// otherwise reported as `EIO`. Enabled by default.
+    pub fn set_hangup_as_eof(&self, enabled: bool) {
+        self.pt.set_hangup_as_eof(enabled);
+    }
+
+    /// Waits until every process holding the other end of the pty has
+    /// closed it, without reading any data from the pty. Note that the
+    /// [`Pts`] returned by [`pts`](Self::pts) also holds the other end of
+    /// the pty open, so it should be dropped once the child has been
+    /// spawned. Returns `false` if `timeout` elapsed first, or waits forever
+    /// if `timeout` is `None`.
+    ///
+    /// # Errors
+    /// Returns an error if we were unable to poll the pty.
+    pub fn wait_hangup(
+        &self,
+        timeout: Option<std::time::Duration>,
+    ) -> crate::Result<bool> {
+        wait_hangup(&self.pt, timeout)
+    }
+
+    /// Returns true if a read from the pty has observed that every process
+    /// holding the other end of the pty has closed it. This can be used to
+    /// tell a hangup apart from other errors or from a read of zero bytes.
+    #[must_use]
+    pub fn is_hung_up(&self) -> bool {
+        self.pt.is_hung_up()
     }
 
     /// Opens a file descriptor for the other end of the pty, which should be
@@ -26,57 +158,189 @@ impl Pty {
     /// Returns an error if the device node to open could not be determined,
     /// or if the device node could not be opened.
     pub fn pts(&self) -> crate::Result<Pts> {
-        Ok(Pts(self.0.pts()?))
+        Ok(Pts(self.pt.pts()?))
+    }
+
+    /// Splits a `Pty` into a read half and a write half, which can be used to
+    /// read from and write to the pty concurrently, including from separate
+    /// threads. The read timeout is kept by the read half and the write
+    /// timeout by the write half. The original `Pty` instance can be
+    /// recovered via the [`OwnedReadPty::unsplit`] method.
+    #[must_use]
+    pub fn into_split(self) -> (OwnedReadPty, OwnedWritePty) {
+        let Self {
+            pt,
+            read_timeout,
+            write_timeout,
+        } = self;
+        let read_pt = std::sync::Arc::new(pt);
+        let write_pt = std::sync::Arc::clone(&read_pt);
+        (
+            OwnedReadPty {
+                pt: read_pt,
+                read_timeout,
+            },
+            OwnedWritePty {
+                pt: write_pt,
+                write_timeout,
+            },
+        )
+    }
+
+    /// Attaches a [`Tap`](crate::Tap), which will observe all data read from
+    /// and written to the pty, through this handle or any other handle to
+    /// the same pty (such as the halves returned by splitting it). Returns
+    /// an id which can be passed to [`remove_tap`](Self::remove_tap).
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.pt.add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) which was previously attached to the
+    /// pty through any handle. Returns false if the tap was not attached.
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.pt.remove_tap(id)
+    }
+
+    /// Starts collecting [`Metrics`](crate::Metrics) for all reads from and
+    /// writes to the pty, through this handle or any other handle to the
+    /// same pty (including the halves returned by splitting it), and returns
+    /// a handle for querying them. The handle can be kept independently of
+    /// the pty. Calling this again returns a handle to the same metrics, and
+    /// metrics cannot be disabled once enabled.
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.pt.enable_metrics()
     }
 }
 
 impl From<Pty> for std::os::fd::OwnedFd {
     fn from(pty: Pty) -> Self {
-        pty.0.into()
+        // the fd is only non-blocking for our own use (see Pty::new)
+        let _ = pty.pt.set_nonblocking(false);
+        pty.pt.into()
     }
 }
 
 impl std::os::fd::AsFd for Pty {
     fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
-        self.0.as_fd()
+        self.pt.as_fd()
     }
 }
 
 impl std::os::fd::AsRawFd for Pty {
     fn as_raw_fd(&self) -> std::os::fd::RawFd {
-        self.0.as_raw_fd()
+        self.pt.as_raw_fd()
+    }
+}
+
+#[cfg(feature = "mio")]
+impl mio::event::Source for Pty {
+    fn register(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> std::io::Result<()> {
+        mio::unix::SourceFd(&std::os::fd::AsRawFd::as_raw_fd(self))
+            .register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> std::io::Result<()> {
+        mio::unix::SourceFd(&std::os::fd::AsRawFd::as_raw_fd(self))
+            .reregister(registry, token, interests)
+    }
+
+    fn deregister(
+        &mut self,
+        registry: &mio::Registry,
+    ) -> std::io::Result<()> {
+        mio::unix::SourceFd(&std::os::fd::AsRawFd::as_raw_fd(self))
+            .deregister(registry)
     }
 }
 
 impl std::io::Read for Pty {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        self.0.read(buf)
+        read_until(&self.pt, self.read_timeout.map(deadline), |mut pt| {
+            pt.read(buf)
+        })
+    }
+
+    fn read_vectored(
+        &mut self,
+        bufs: &mut [std::io::IoSliceMut<'_>],
+    ) -> std::io::Result<usize> {
+        read_until(&self.pt, self.read_timeout.map(deadline), |mut pt| {
+            pt.read_vectored(bufs)
+        })
     }
 }
 
 impl std::io::Write for Pty {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.0.write(buf)
+        write_until(&self.pt, self.write_timeout.map(deadline), |mut pt| {
+            pt.write(buf)
+        })
+    }
+
+    fn write_vectored(
+        &mut self,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> std::io::Result<usize> {
+        write_until(&self.pt, self.write_timeout.map(deadline), |mut pt| {
+            pt.write_vectored(bufs)
+        })
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
-        self.0.flush()
+        self.pt.flush()
     }
 }
 
 impl std::io::Read for &Pty {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        (&self.0).read(buf)
+        read_until(&self.pt, self.read_timeout.map(deadline), |mut pt| {
+            pt.read(buf)
+        })
+    }
+
+    fn read_vectored(
+        &mut self,
+        bufs: &mut [std::io::IoSliceMut<'_>],
+    ) -> std::io::Result<usize> {
+        read_until(&self.pt, self.read_timeout.map(deadline), |mut pt| {
+            pt.read_vectored(bufs)
+        })
     }
 }
 
 impl std::io::Write for &Pty {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        (&self.0).write(buf)
+        write_until(&self.pt, self.write_timeout.map(deadline), |mut pt| {
+            pt.write(buf)
+        })
+    }
+
+    fn write_vectored(
+        &mut self,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> std::io::Result<usize> {
+        write_until(&self.pt, self.write_timeout.map(deadline), |mut pt| {
+            pt.write_vectored(bufs)
+        })
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
-        (&self.0).flush()
+        (&self.pt).flush()
     }
 }
 
@@ -84,3 +348,367 @@ impl std::io::Write for &Pty {
 ///
 /// See [`Pty::pts`] and [`Command::spawn`](crate::blocking::Command::spawn)
 pub struct Pts(pub(crate) crate::sys::Pts);
+
+/// Owned read half of a [`Pty`]
+#[derive(Debug)]
+pub struct OwnedReadPty {
+    pt: std::sync::Arc<crate::sys::Pty>,
+    read_timeout: Option<std::time::Duration>,
+}
+
+impl OwnedReadPty {
+    /// Attempt to join the two halves of a `Pty` back into a single instance.
+    /// The two halves must have originated from calling
+    /// [`into_split`](Pty::into_split) on a single instance.
+    ///
+    /// # Errors
+    /// Returns an error if the two halves came from different [`Pty`]
+    /// instances. The mismatched halves are returned as part of the error.
+    pub fn unsplit(
+        self,
+        write_half: OwnedWritePty,
+    ) -> Result<Pty, crate::UnsplitError<Self, OwnedWritePty>> {
+        if std::sync::Arc::ptr_eq(&self.pt, &write_half.pt) {
+            let Self {
+                pt: read_pt,
+                read_timeout,
+            } = self;
+            let OwnedWritePty {
+                pt: write_pt,
+                write_timeout,
+            } = write_half;
+            drop(write_pt);
+            Ok(Pty {
+                pt: std::sync::Arc::try_unwrap(read_pt)
+                    // it shouldn't be possible for more than two references
+                    // to the same pty to exist
+                    .unwrap_or_else(|_| unreachable!()),
+                read_timeout,
+                write_timeout,
+            })
+        } else {
+            Err(crate::UnsplitError(self, write_half))
+        }
+    }
+
+    /// Sets the timeout for reads from the pty. See
+    /// [`Pty::set_read_timeout`].
+    pub fn set_read_timeout(&mut self, timeout: Option<std::time::Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    /// Returns the timeout for reads from the pty. See
+    /// [`Pty::set_read_timeout`].
+    #[must_use]
+    pub fn read_timeout(&self) -> Option<std::time::Duration> {
+        self.read_timeout
+    }
+
+    /// Reads from the pty, failing with an error of kind
+    /// [`std::io::ErrorKind::TimedOut`] if no data is available before
+    /// `deadline`. See [`Pty::read_with_deadline`].
+    ///
+    /// # Errors
+    /// Returns an error if the deadline passes, or if reading from the pty
+    /// fails.
+    pub fn read_with_deadline(
+        &self,
+        buf: &mut [u8],
+        deadline: std::time::Instant,
+    ) -> std::io::Result<usize> {
+        read_until(&self.pt, Some(deadline), |mut pt| pt.read(buf))
+    }
+
+    /// Controls whether reading from the pty after every process holding the
+    /// other end of the pty has closed it reports end of file. See
+    /// [`Pty::set_hangup_as_eof`].
+    pub fn set_hangup_as_eof(&self, enabled: bool) {
+        self.pt.set_hangup_as_eof(enabled);
+    }
+
+    /// Returns true if a read from the pty has observed that every process
+    /// holding the other end of the pty has closed it. See
+    /// [`Pty::is_hung_up`].
+    #[must_use]
+    pub fn is_hung_up(&self) -> bool {
+        self.pt.is_hung_up()
+    }
+
+    /// Waits until every process holding the other end of the pty has
+    /// closed it, without reading any data from the pty. See
+    /// [`Pty::wait_hangup`].
+    ///
+    /// # Errors
+    /// Returns an error if we were unable to poll the pty.
+    pub fn wait_hangup(
+        &self,
+        timeout: Option<std::time::Duration>,
+    ) -> crate::Result<bool> {
+        wait_hangup(&self.pt, timeout)
+    }
+
+    /// Puts the pty into non-blocking mode. See [`Pty::set_nonblocking`].
+    pub fn set_nonblocking(&self, nonblocking: bool) {
+        self.pt.set_would_block(nonblocking);
+    }
+
+    /// Returns true if the pty is in non-blocking mode. See
+    /// [`Pty::set_nonblocking`].
+    #[must_use]
+    pub fn is_nonblocking(&self) -> bool {
+        self.pt.would_block()
+    }
+
+    /// Attaches a [`Tap`](crate::Tap) to the pty. See [`Pty::add_tap`].
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.pt.add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) from the pty. See
+    /// [`Pty::remove_tap`].
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.pt.remove_tap(id)
+    }
+
+    /// Starts collecting metrics for the pty, and returns a handle for
+    /// querying them. See [`Pty::enable_metrics`].
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.pt.enable_metrics()
+    }
+}
+
+impl std::io::Read for OwnedReadPty {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        read_until(&self.pt, self.read_timeout.map(deadline), |mut pt| {
+            pt.read(buf)
+        })
+    }
+
+    fn read_vectored(
+        &mut self,
+        bufs: &mut [std::io::IoSliceMut<'_>],
+    ) -> std::io::Result<usize> {
+        read_until(&self.pt, self.read_timeout.map(deadline), |mut pt| {
+            pt.read_vectored(bufs)
+        })
+    }
+}
+
+#[cfg(feature = "mio")]
+impl mio::event::Source for OwnedReadPty {
+    fn register(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> std::io::Result<()> {
+        mio::unix::SourceFd(&std::os::fd::AsRawFd::as_raw_fd(&*self.pt))
+            .register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> std::io::Result<()> {
+        mio::unix::SourceFd(&std::os::fd::AsRawFd::as_raw_fd(&*self.pt))
+            .reregister(registry, token, interests)
+    }
+
+    fn deregister(
+        &mut self,
+        registry: &mio::Registry,
+    ) -> std::io::Result<()> {
+        mio::unix::SourceFd(&std::os::fd::AsRawFd::as_raw_fd(&*self.pt))
+            .deregister(registry)
+    }
+}
+
+/// Owned write half of a [`Pty`]
+#[derive(Debug)]
+pub struct OwnedWritePty {
+    pt: std::sync::Arc<crate::sys::Pty>,
+    write_timeout: Option<std::time::Duration>,
+}
+
+impl OwnedWritePty {
+    /// Change the terminal size associated with the pty.
+    ///
+    /// # Errors
+    /// Returns an error if we were unable to set the terminal size.
+    pub fn resize(&self, size: crate::Size) -> crate::Result<()> {
+        self.pt.set_term_size(size)
+    }
+
+    /// Sets the timeout for writes to the pty. See
+    /// [`Pty::set_write_timeout`].
+    pub fn set_write_timeout(
+        &mut self,
+        timeout: Option<std::time::Duration>,
+    ) {
+        self.write_timeout = timeout;
+    }
+
+    /// Returns the timeout for writes to the pty. See
+    /// [`Pty::set_write_timeout`].
+    #[must_use]
+    pub fn write_timeout(&self) -> Option<std::time::Duration> {
+        self.write_timeout
+    }
+
+    /// Puts the pty into non-blocking mode. See [`Pty::set_nonblocking`].
+    pub fn set_nonblocking(&self, nonblocking: bool) {
+        self.pt.set_would_block(nonblocking);
+    }
+
+    /// Returns true if the pty is in non-blocking mode. See
+    /// [`Pty::set_nonblocking`].
+    #[must_use]
+    pub fn is_nonblocking(&self) -> bool {
+        self.pt.would_block()
+    }
+
+    /// Attaches a [`Tap`](crate::Tap) to the pty. See [`Pty::add_tap`].
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.pt.add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) from the pty. See
+    /// [`Pty::remove_tap`].
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.pt.remove_tap(id)
+    }
+
+    /// Starts collecting metrics for the pty, and returns a handle for
+    /// querying them. See [`Pty::enable_metrics`].
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.pt.enable_metrics()
+    }
+}
+
+impl std::io::Write for OwnedWritePty {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        write_until(&self.pt, self.write_timeout.map(deadline), |mut pt| {
+            pt.write(buf)
+        })
+    }
+
+    fn write_vectored(
+        &mut self,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> std::io::Result<usize> {
+        write_until(&self.pt, self.write_timeout.map(deadline), |mut pt| {
+            pt.write_vectored(bufs)
+        })
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        (&*self.pt).flush()
+    }
+}
+
+#[cfg(feature = "mio")]
+impl mio::event::Source for OwnedWritePty {
+    fn register(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> std::io::Result<()> {
+        mio::unix::SourceFd(&std::os::fd::AsRawFd::as_raw_fd(&*self.pt))
+            .register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> std::io::Result<()> {
+        mio::unix::SourceFd(&std::os::fd::AsRawFd::as_raw_fd(&*self.pt))
+            .reregister(registry, token, interests)
+    }
+
+    fn deregister(
+        &mut self,
+        registry: &mio::Registry,
+    ) -> std::io::Result<()> {
+        mio::unix::SourceFd(&std::os::fd::AsRawFd::as_raw_fd(&*self.pt))
+            .deregister(registry)
+    }
+}
+
+fn deadline(timeout: std::time::Duration) -> std::time::Instant {
+    std::time::Instant::now() + timeout
+}
+
+// the fd is always in non-blocking mode, so reads and writes wait for
+// readiness via poll and retry whenever they would block, unless
+// set_nonblocking was called, in which case WouldBlock is reported to the
+// caller.
+fn wait_hangup(
+    pt: &crate::sys::Pty,
+    timeout: Option<std::time::Duration>,
+) -> crate::Result<bool> {
+    Ok(pt
+        .poll(rustix::event::PollFlags::empty(), timeout.map(deadline))?
+        .contains(rustix::event::PollFlags::HUP))
+}
+
+fn read_until(
+    pt: &crate::sys::Pty,
+    deadline: Option<std::time::Instant>,
+    read: impl FnMut(&crate::sys::Pty) -> std::io::Result<usize>,
+) -> std::io::Result<usize> {
+    retry(pt, rustix::event::PollFlags::IN, deadline, read)
+}
+
+fn write_until(
+    pt: &crate::sys::Pty,
+    deadline: Option<std::time::Instant>,
+    write: impl FnMut(&crate::sys::Pty) -> std::io::Result<usize>,
+) -> std::io::Result<usize> {
+    retry(pt, rustix::event::PollFlags::OUT, deadline, write)
+}
+
+fn retry(
+    pt: &crate::sys::Pty,
+    events: rustix::event::PollFlags,
+    deadline: Option<std::time::Instant>,
+    mut f: impl FnMut(&crate::sys::Pty) -> std::io::Result<usize>,
+) -> std::io::Result<usize> {
+    if pt.would_block() {
+        return f(pt);
+    }
+    loop {
+        match f(pt) {
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                wait_ready(pt, events, deadline)?;
+            }
+            res => return res,
+        }
+    }
+}
+
+fn wait_ready(
+    pt: &crate::sys::Pty,
+    events: rustix::event::PollFlags,
+    deadline: Option<std::time::Instant>,
+) -> std::io::Result<()> {
+    if pt.poll(events, deadline)?.is_empty() {
+        Err(std::io::ErrorKind::TimedOut.into())
+    } else {
+        Ok(())
+    }
+}
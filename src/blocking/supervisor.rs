@@ -0,0 +1,277 @@
+/// Supervises the whole session of a child spawned on a pty, rather than
+/// just the child itself (Linux only)
+///
+/// Programs which daemonize helper processes can exit while the helpers
+/// keep running (and keep the pts open). Creating a `Supervisor` marks the
+/// calling process as a child subreaper (see `PR_SET_CHILD_SUBREAPER` in
+/// `prctl(2)`), so that any orphaned descendants of children spawned
+/// afterwards are reparented to it rather than to init, and
+/// [`wait`](Self::wait) waits until every process in the child's session
+/// has exited. Note that descendants which start a new session of their
+/// own are no longer part of the child's session, and so are neither waited
+/// for nor reaped.
+pub struct Supervisor {
+    _private: (),
+}
+
+impl Supervisor {
+    /// Marks the calling process as a child subreaper. This affects the
+    /// entire calling process, and lasts until the process exits.
+    ///
+    /// # Errors
+    /// Returns an error if the subreaper attribute can't be set.
+    pub fn new() -> crate::Result<Self> {
+        rustix::process::set_child_subreaper(
+            Some(rustix::process::getpid()),
+        )?;
+        Ok(Self { _private: () })
+    }
+
+    /// Waits until every process in the session of `child` has exited,
+    /// reaping the child and any descendants which are reparented to the
+    /// calling process. `child` must be the leader of its session, as it is
+    /// when spawned with
+    /// [`SessionMode::NewSession`](crate::SessionMode::NewSession) or
+    /// [`SessionMode::NoControllingTerminal`](crate::SessionMode::NoControllingTerminal).
+    ///
+    /// Only children of the calling process which are in the session of
+    /// `child` are reaped, so other children can still be waited for
+    /// elsewhere. Processes in the session which are reaped by something
+    /// else while waiting are left out of the result.
+    ///
+    /// # Errors
+    /// Returns an error if `child` isn't the leader of its own session, or
+    /// if waiting for children fails.
+    pub fn wait(
+        &self,
+        child: std::process::Child,
+    ) -> crate::Result<SessionStatus> {
+        let sid = rustix::process::Pid::from_child(&child);
+        // the child is reaped below, so the handle is no longer useful
+        drop(child);
+        if rustix::process::getsid(Some(sid))? != sid {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "child is not a session leader",
+            )
+            .into());
+        }
+
+        let mut leader = None;
+        let mut descendants = vec![];
+        let mut waited = None;
+        let leader = loop {
+            let session = Session::scan(sid)?;
+            for &pid in &session.exited {
+                let Some(status) = reap(pid)? else {
+                    if pid == sid {
+                        // nothing else can report how the child exited
+                        return Err(std::io::Error::from_raw_os_error(
+                            libc::ECHILD,
+                        )
+                        .into());
+                    }
+                    continue;
+                };
+                if pid == sid {
+                    leader = Some(status);
+                } else {
+                    descendants.push((pid_to_u32(pid), status));
+                }
+            }
+            if session.running.is_empty() {
+                // the leader is always reaped above once it has exited
+                break leader.ok_or_else(|| {
+                    std::io::Error::from_raw_os_error(libc::ECHILD)
+                })?;
+            }
+            // a child outside of the session which has exited (and which is
+            // left for something else to reap) makes waitid return straight
+            // away, as does having no children at all, so fall back to
+            // waiting on the processes in the session directly then
+            waited =
+                if waited.is_some_and(|pid| !session.exited.contains(&pid)) {
+                    None
+                } else {
+                    wait_exited()?
+                };
+            if waited.is_none() {
+                session.wait_running()?;
+            }
+        };
+
+        Ok(SessionStatus {
+            leader,
+            descendants,
+        })
+    }
+}
+
+/// The result of [`Supervisor::wait`]
+#[derive(Debug, Clone)]
+pub struct SessionStatus {
+    leader: std::process::ExitStatus,
+    descendants: Vec<(u32, std::process::ExitStatus)>,
+}
+
+impl SessionStatus {
+    /// Returns the exit status of the child which was spawned (the session
+    /// leader).
+    #[must_use]
+    pub fn leader(&self) -> std::process::ExitStatus {
+        self.leader
+    }
+
+    /// Returns the pids and exit statuses of the other processes in the
+    /// session which were reaped by the calling process, in the order they
+    /// were reaped. Processes which were reaped by their own parents are not
+    /// included.
+    #[must_use]
+    pub fn descendants(&self) -> &[(u32, std::process::ExitStatus)] {
+        &self.descendants
+    }
+}
+
+fn pid_to_u32(pid: rustix::process::Pid) -> u32 {
+    pid.as_raw_nonzero().get().unsigned_abs()
+}
+
+// reaps the given child, which must have already exited. returns None if
+// it was reaped by something else first.
+fn reap(
+    pid: rustix::process::Pid,
+) -> crate::Result<Option<std::process::ExitStatus>> {
+    use std::os::unix::process::ExitStatusExt as _;
+
+    let mut status = 0;
+    loop {
+        if unsafe {
+            libc::waitpid(pid.as_raw_nonzero().get(), &raw mut status, 0)
+        } != -1
+        {
+            return Ok(Some(std::process::ExitStatus::from_raw(status)));
+        }
+        let e = std::io::Error::last_os_error();
+        match e.raw_os_error() {
+            Some(libc::ECHILD) => return Ok(None),
+            Some(libc::EINTR) => {}
+            _ => return Err(e.into()),
+        }
+    }
+}
+
+// blocks until any child has exited, without reaping it. returns the pid of
+// the child, or None if there are no children to wait for.
+fn wait_exited() -> crate::Result<Option<rustix::process::Pid>> {
+    loop {
+        let mut info = unsafe { std::mem::zeroed::<libc::siginfo_t>() };
+        if unsafe {
+            libc::waitid(
+                libc::P_ALL,
+                0,
+                &raw mut info,
+                libc::WEXITED | libc::WNOWAIT,
+            )
+        } != -1
+        {
+            // Safety: waitid filled in the siginfo for a child
+            let pid = unsafe { info.si_pid() };
+            return Ok(rustix::process::Pid::from_raw(pid));
+        }
+        let e = std::io::Error::last_os_error();
+        match e.raw_os_error() {
+            Some(libc::ECHILD) => return Ok(None),
+            Some(libc::EINTR) => {}
+            _ => return Err(e.into()),
+        }
+    }
+}
+
+// the state of a session, as seen from the calling process
+struct Session {
+    // processes in the session which are still running (zombies are not
+    // counted, since they will be reaped by whichever process is their
+    // parent)
+    running: Vec<rustix::process::Pid>,
+    // children of the calling process in the session which have exited but
+    // not been reaped yet
+    exited: Vec<rustix::process::Pid>,
+}
+
+impl Session {
+    fn scan(sid: rustix::process::Pid) -> crate::Result<Self> {
+        let pid = rustix::process::getpid().as_raw_nonzero().get();
+        let sid = sid.as_raw_nonzero().get();
+        let mut session = Self {
+            running: vec![],
+            exited: vec![],
+        };
+        for entry in std::fs::read_dir("/proc")? {
+            let entry = entry?;
+            let Some(child) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse::<i32>().ok())
+                .and_then(rustix::process::Pid::from_raw)
+            else {
+                continue;
+            };
+            // processes can exit while we are looking at them
+            let Ok(stat) = std::fs::read_to_string(entry.path().join("stat"))
+            else {
+                continue;
+            };
+            // the command name can contain anything (including spaces and
+            // parens), so parse the fields after the last paren
+            let Some((_, fields)) = stat.rsplit_once(')') else {
+                continue;
+            };
+            let mut fields = fields.split_whitespace();
+            let state = fields.next();
+            let ppid = fields.next().and_then(|s| s.parse::<i32>().ok());
+            if fields.nth(1).and_then(|s| s.parse::<i32>().ok()) != Some(sid)
+            {
+                continue;
+            }
+            if state != Some("Z") {
+                session.running.push(child);
+            } else if ppid == Some(pid) {
+                session.exited.push(child);
+            }
+        }
+        Ok(session)
+    }
+
+    // blocks until any of the running processes in the session exits (the
+    // processes don't need to be children of the calling process for this)
+    fn wait_running(&self) -> crate::Result<()> {
+        let mut pidfds = vec![];
+        for &pid in &self.running {
+            match rustix::process::pidfd_open(
+                pid,
+                rustix::process::PidfdFlags::empty(),
+            ) {
+                Ok(pidfd) => pidfds.push(pidfd),
+                // it has already exited
+                Err(rustix::io::Errno::SRCH) => return Ok(()),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let mut fds: Vec<_> = pidfds
+            .iter()
+            .map(|pidfd| {
+                rustix::event::PollFd::new(
+                    pidfd,
+                    rustix::event::PollFlags::IN,
+                )
+            })
+            .collect();
+        loop {
+            match rustix::event::poll(&mut fds, -1) {
+                Ok(_) => return Ok(()),
+                Err(rustix::io::Errno::INTR) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
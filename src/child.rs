@@ -0,0 +1,678 @@
+use std::os::fd::AsRawFd as _;
+
+type PreExec =
+    Box<dyn FnMut() -> std::io::Result<()> + Send + Sync + 'static>;
+
+// everything which happens in the child between fork and exec, shared by
+// the different Command types. a single hook is installed on the underlying
+// std::process::Command when it is created, and it runs whatever was
+// configured at the time the child was spawned.
+pub struct Setup {
+    state: std::sync::Arc<std::sync::Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    session_mode: crate::SessionMode,
+    #[cfg(target_os = "linux")]
+    parent_death_signal: Option<i32>,
+    job: Option<Job>,
+    rlimits: Vec<(crate::Resource, rustix::process::Rlimit)>,
+    umask: Option<rustix::fs::Mode>,
+    nice: Option<i32>,
+    #[cfg(target_os = "linux")]
+    io_priority: Option<crate::IoPriority>,
+    user: Option<crate::User>,
+    // resolved from user when spawning
+    login: Option<crate::login::Login>,
+    // variables which the caller set or removed on the command, which the
+    // environment set when spawning must not override
+    caller_env: std::collections::HashSet<std::ffi::OsString>,
+    // variables to set when spawning unless the caller sets them
+    default_env: Vec<(&'static str, std::ffi::OsString)>,
+    // whether to set COLUMNS and LINES when spawning
+    size_env: bool,
+    fds: Vec<(std::os::fd::RawFd, std::os::fd::OwnedFd)>,
+    pre_exec: Option<PreExec>,
+    spawn: Option<Spawn>,
+}
+
+// how to place a child which is part of a job (see blocking::JobControl)
+// into the job's process group. this overrides the session mode.
+#[derive(Clone, Copy)]
+pub struct Job {
+    // None to start a new process group
+    pub pgid: Option<rustix::process::Pid>,
+    pub foreground: bool,
+}
+
+// the parts of the setup which are specific to a single spawn. everything
+// here is prepared in the parent, so that the child only has to make
+// syscalls.
+struct Spawn {
+    pts: std::os::fd::RawFd,
+    #[cfg(target_os = "linux")]
+    parent: rustix::process::Pid,
+    report: std::os::fd::OwnedFd,
+    // duplicates of the mapped fds (and of the report pipe), all numbered
+    // above every fd which will be mapped in the child, so that mapping one
+    // fd can't clobber another
+    fds: Vec<(std::os::fd::RawFd, std::os::fd::OwnedFd)>,
+}
+
+impl Setup {
+    pub fn new(cmd: &mut std::process::Command) -> Self {
+        use std::os::unix::process::CommandExt as _;
+
+        let state =
+            std::sync::Arc::new(std::sync::Mutex::new(State::default()));
+        let child_state = std::sync::Arc::clone(&state);
+        // Safety: the lock can't be held by anything else while the child
+        // is being spawned, since spawn only releases it right before
+        // spawning, so try_lock can't fail (and it only performs an atomic
+        // compare and swap, which is async-signal-safe). See State::run for
+        // the safety of the hook itself.
+        unsafe {
+            cmd.pre_exec(move || {
+                child_state
+                    .try_lock()
+                    .map_err(|_| {
+                        std::io::Error::from(std::io::ErrorKind::Other)
+                    })?
+                    .run()
+            })
+        };
+        Self { state }
+    }
+
+    pub fn session_mode(&self, mode: crate::SessionMode) {
+        self.state().session_mode = mode;
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn kill_on_parent_death(&self, signal: i32) {
+        self.state().parent_death_signal = Some(signal);
+    }
+
+    pub fn rlimit(
+        &self,
+        resource: crate::Resource,
+        soft: Option<u64>,
+        hard: Option<u64>,
+    ) {
+        let mut state = self.state();
+        state.rlimits.retain(|(r, _)| *r != resource);
+        state.rlimits.push((
+            resource,
+            rustix::process::Rlimit {
+                current: soft,
+                maximum: hard,
+            },
+        ));
+    }
+
+    pub fn umask(&self, mask: u32) {
+        // only the permission bits are meaningful, and the raw mode type
+        // is narrower than u32 on some platforms
+        let mask = u16::try_from(mask & 0o7777).unwrap_or_default();
+        self.state().umask = Some(rustix::fs::Mode::from_raw_mode(
+            rustix::fs::RawMode::from(mask),
+        ));
+    }
+
+    pub fn nice(&self, increment: i32) {
+        self.state().nice = Some(increment);
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn io_priority(&self, priority: crate::IoPriority) {
+        assert!(
+            priority.level() <= 7,
+            "I/O priority levels range from 0 to 7"
+        );
+        self.state().io_priority = Some(priority);
+    }
+
+    pub fn as_user(&self, user: crate::User) {
+        self.state().user = Some(user);
+    }
+
+    pub fn default_env(&self, env: Vec<(&'static str, std::ffi::OsString)>) {
+        self.state().default_env = env;
+    }
+
+    pub fn size_env(&self) {
+        self.state().size_env = true;
+    }
+
+    // records that the caller set or removed key on the command
+    pub fn caller_env(&self, key: &std::ffi::OsStr) {
+        self.state().caller_env.insert(key.to_os_string());
+    }
+
+    pub fn caller_env_clear(&self) {
+        self.state().caller_env.clear();
+    }
+
+    // looks up the user passed to as_user (if any). returns the environment
+    // variables which the caller should set on the command: the defaults
+    // from default_env, the current size of the pty if requested via
+    // size_env, and the login environment for the user. variables which the
+    // caller set themselves are left out, and since they are recorded
+    // separately, the variables set here don't prevent them from being
+    // updated by the next spawn.
+    pub fn prepare_env(
+        &self,
+        pts: &crate::sys::Pts,
+    ) -> crate::Result<Vec<(&'static str, std::ffi::OsString)>> {
+        let res = self.env(pts);
+        if res.is_err() {
+            // spawning has failed, so the mapped fds are closed just like
+            // they are in spawn
+            self.state().fds.clear();
+        }
+        res
+    }
+
+    fn env(
+        &self,
+        pts: &crate::sys::Pts,
+    ) -> crate::Result<Vec<(&'static str, std::ffi::OsString)>> {
+        let (user, mut env, size_env) = {
+            let state = self.state();
+            (
+                state.user.clone(),
+                state.default_env.clone(),
+                state.size_env,
+            )
+        };
+        if size_env {
+            let size = rustix::termios::tcgetwinsize(pts)?;
+            env.push(("COLUMNS", size.ws_col.to_string().into()));
+            env.push(("LINES", size.ws_row.to_string().into()));
+        }
+        if let Some(user) = user {
+            let (login, passwd) = crate::login::Passwd::lookup(&user)
+                .and_then(|passwd| Ok((passwd.login()?, passwd)))
+                .map_err(|e| {
+                    crate::Error::operation_failed(
+                        crate::Operation::LookupUser,
+                        Some(pts.name()),
+                        None,
+                        e,
+                    )
+                })?;
+            self.state().login = Some(login);
+            env.extend(passwd.env());
+        }
+        let state = self.state();
+        env.retain(|(key, _)| {
+            !state.caller_env.contains(std::ffi::OsStr::new(key))
+        });
+        drop(state);
+        Ok(env)
+    }
+
+    pub fn job(&self, job: Option<Job>) {
+        self.state().job = job;
+    }
+
+    pub fn fd_map(
+        &self,
+        child_fd: std::os::fd::RawFd,
+        fd: std::os::fd::OwnedFd,
+    ) {
+        let mut state = self.state();
+        state.fds.retain(|(target, _)| *target != child_fd);
+        state.fds.push((child_fd, fd));
+    }
+
+    pub fn pre_exec(&self, f: PreExec) {
+        self.state().pre_exec = Some(f);
+    }
+
+    // spawns the child via the given function, after preparing the setup
+    // which it should run. if spawning fails during the setup in the child,
+    // the step which failed is read from the child's report.
+    pub fn spawn<T>(
+        &self,
+        pts: &crate::sys::Pts,
+        spawn: impl FnOnce() -> std::io::Result<T>,
+        child_pid: impl FnOnce(&T) -> Option<u32>,
+    ) -> crate::Result<T> {
+        // the pts is only given to the user once everything else is ready,
+        // and given back if spawning fails
+        let res = self.chown_pts(pts).and_then(|owner| {
+            let res = self.spawn_chowned(pts, spawn, child_pid);
+            if let (Err(_), Some(owner)) = (&res, owner) {
+                let _ = owner.restore(pts);
+            }
+            res
+        });
+        // the mapped fds belong to this child, and the parent's copies need
+        // to be closed so that it can see when the child closes them
+        self.state().fds.clear();
+        res
+    }
+
+    fn chown_pts(
+        &self,
+        pts: &crate::sys::Pts,
+    ) -> crate::Result<Option<crate::login::Owner>> {
+        let Some(login) = self.state().login.clone() else {
+            return Ok(None);
+        };
+        crate::login::chown_pts(pts, &login).map(Some).map_err(|e| {
+            crate::Error::operation_failed(
+                crate::Operation::ChownPts,
+                Some(pts.name()),
+                None,
+                e,
+            )
+        })
+    }
+
+    fn spawn_chowned<T>(
+        &self,
+        pts: &crate::sys::Pts,
+        spawn: impl FnOnce() -> std::io::Result<T>,
+        child_pid: impl FnOnce(&T) -> Option<u32>,
+    ) -> crate::Result<T> {
+        let steps = Steps::new()?;
+        let prepared = self.prepare(pts, &steps);
+        let res = prepared.map(|()| spawn());
+        self.state().spawn = None;
+        match res? {
+            Ok(child) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    pty = %pts.name().display(),
+                    pid = child_pid(&child),
+                    "spawned child"
+                );
+                #[cfg(not(feature = "tracing"))]
+                let _ = child_pid;
+                Ok(child)
+            }
+            Err(e) => {
+                let (operation, pid) = steps
+                    .take()
+                    .map_or((crate::Operation::Spawn, None), |(op, pid)| {
+                        (op, Some(pid))
+                    });
+                Err(crate::Error::operation_failed(
+                    operation,
+                    Some(pts.name()),
+                    pid,
+                    e,
+                ))
+            }
+        }
+    }
+
+    fn prepare(
+        &self,
+        pts: &crate::sys::Pts,
+        steps: &Steps,
+    ) -> crate::Result<()> {
+        let (fds, report) = {
+            let state = self.state();
+            let min = state
+                .fds
+                .iter()
+                .map(|(target, _)| target.saturating_add(1))
+                .max()
+                .unwrap_or(0)
+                .max(0);
+            let fds = state
+                .fds
+                .iter()
+                .map(|(target, fd)| {
+                    Ok((*target, rustix::io::fcntl_dupfd_cloexec(fd, min)?))
+                })
+                .collect::<rustix::io::Result<_>>()?;
+            drop(state);
+            (fds, rustix::io::fcntl_dupfd_cloexec(&steps.write, min)?)
+        };
+        self.state().spawn = Some(Spawn {
+            pts: pts.as_raw_fd(),
+            #[cfg(target_os = "linux")]
+            parent: rustix::process::getpid(),
+            report,
+            fds,
+        });
+        Ok(())
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl State {
+    // this runs in the child after forking, so it must only do
+    // async-signal-safe things. setsid(), setpgid(), getpgrp() and
+    // pthread_sigmask() are async-signal-safe functions, and ioctl(),
+    // setgid() and setuid() are async-signal-safe functions (and the child
+    // only has a single thread, so they don't need to synchronize with
+    // other threads), and prctl(), kill(), setrlimit(), umask(), nice(),
+    // ioprio_set(), setgroups(), dup2(), getpid(), getppid() and write() are
+    // raw syscalls (which are inherently async-signal-safe). the user's
+    // pre_exec hook is covered by the safety requirements of
+    // Command::pre_exec.
+    fn run(&mut self) -> std::io::Result<()> {
+        let Some(spawn) = &self.spawn else {
+            return Ok(());
+        };
+        let reporter = Reporter(spawn.report.as_raw_fd());
+
+        if let Some(job) = self.job {
+            rustix::process::setpgid(None, job.pgid).inspect_err(|_| {
+                reporter.failed(crate::Operation::SetProcessGroup);
+            })?;
+            if job.foreground {
+                set_foreground(
+                    unsafe { std::os::fd::BorrowedFd::borrow_raw(spawn.pts) },
+                    rustix::process::getpgrp(),
+                )
+                .inspect_err(|_| {
+                    reporter.failed(crate::Operation::SetForeground);
+                })?;
+            }
+        } else {
+            self.setup_session(spawn.pts, reporter)?;
+        }
+
+        self.set_limits(reporter)?;
+        if let Some(login) = &self.login {
+            switch_user(login, reporter)?;
+        }
+
+        // this happens after switching users, since changing credentials
+        // clears the parent death signal
+        #[cfg(target_os = "linux")]
+        if let Some(signal) = self.parent_death_signal {
+            set_parent_death_signal(signal, spawn.parent).inspect_err(
+                |_| {
+                    reporter.failed(crate::Operation::ParentDeathSignal);
+                },
+            )?;
+        }
+
+        // this happens after setting the controlling terminal, since the
+        // pts could be one of the fds which is replaced. dup2 clears
+        // CLOEXEC on the new fd, and the duplicates we are mapping from
+        // are all CLOEXEC, so they will be closed on exec.
+        for (target, fd) in &spawn.fds {
+            if unsafe { libc::dup2(fd.as_raw_fd(), *target) } == -1 {
+                reporter.failed(crate::Operation::FdMap);
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+
+        if let Some(custom) = &mut self.pre_exec {
+            custom().inspect_err(|_| {
+                reporter.failed(crate::Operation::PreExec);
+            })?;
+        }
+
+        Ok(())
+    }
+
+    // this also runs in the child (see run)
+    fn set_limits(&self, reporter: Reporter) -> std::io::Result<()> {
+        for (resource, limit) in &self.rlimits {
+            rustix::process::setrlimit((*resource).into(), *limit)
+                .inspect_err(|_| {
+                    reporter.failed(crate::Operation::SetResourceLimit);
+                })?;
+        }
+        if let Some(mask) = self.umask {
+            rustix::process::umask(mask);
+        }
+        if let Some(increment) = self.nice {
+            rustix::process::nice(increment).inspect_err(|_| {
+                reporter.failed(crate::Operation::Nice);
+            })?;
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(priority) = self.io_priority {
+            // there is no libc wrapper for ioprio_set. 1 is
+            // IOPRIO_WHO_PROCESS, and 0 is the calling process.
+            if unsafe {
+                libc::syscall(libc::SYS_ioprio_set, 1, 0, priority.to_raw())
+            } == -1
+            {
+                reporter.failed(crate::Operation::SetIoPriority);
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    // this also runs in the child (see run)
+    fn setup_session(
+        &self,
+        pts: std::os::fd::RawFd,
+        reporter: Reporter,
+    ) -> std::io::Result<()> {
+        match self.session_mode {
+            crate::SessionMode::NewSession => {
+                rustix::process::setsid().inspect_err(|_| {
+                    reporter.failed(crate::Operation::Setsid);
+                })?;
+                rustix::process::ioctl_tiocsctty(unsafe {
+                    std::os::fd::BorrowedFd::borrow_raw(pts)
+                })
+                .inspect_err(|_| {
+                    reporter.failed(crate::Operation::SetControllingTerminal);
+                })?;
+            }
+            crate::SessionMode::NewProcessGroup => {
+                rustix::process::setpgid(None, None).inspect_err(|_| {
+                    reporter.failed(crate::Operation::SetProcessGroup);
+                })?;
+            }
+            crate::SessionMode::NoControllingTerminal => {
+                rustix::process::setsid().inspect_err(|_| {
+                    reporter.failed(crate::Operation::Setsid);
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// a pipe which the child uses to report which step of its setup failed,
+// since the error returned from spawning only carries the errno. a new pipe
+// is created for each spawn and closed afterwards. both ends are
+// close-on-exec, and the parent only reads from it after a failed spawn (by
+// which point the child has already written to it and exited).
+struct Steps {
+    read: std::os::fd::OwnedFd,
+    write: std::os::fd::OwnedFd,
+}
+
+impl Steps {
+    fn new() -> crate::Result<Self> {
+        let (read, write) = pipe()?;
+        let mut opts = rustix::fs::fcntl_getfl(&read)?;
+        opts |= rustix::fs::OFlags::NONBLOCK;
+        rustix::fs::fcntl_setfl(&read, opts)?;
+        Ok(Self { read, write })
+    }
+
+    // returns the step which failed and the pid of the child which reported
+    // it, if the child reported anything
+    fn take(&self) -> Option<(crate::Operation, u32)> {
+        let mut report = None;
+        let mut buf = [0; 5];
+        while rustix::io::read(&self.read, &mut buf) == Ok(5) {
+            let [code, pid @ ..] = buf;
+            report = crate::Operation::from_code(code)
+                .zip(u32::try_from(i32::from_ne_bytes(pid)).ok());
+        }
+        report
+    }
+}
+
+// switches to the given user's credentials, in the same order as login:
+// the groups have to be set while the process is still privileged. this
+// runs in the child (see State::run).
+fn switch_user(
+    login: &crate::login::Login,
+    reporter: Reporter,
+) -> std::io::Result<()> {
+    #[cfg(not(target_vendor = "apple"))]
+    let len = login.groups.len();
+    #[cfg(target_vendor = "apple")]
+    let len = libc::c_int::try_from(login.groups.len())
+        .map_err(|_| std::io::Error::from_raw_os_error(libc::EINVAL))?;
+    for (res, operation) in [
+        (
+            unsafe { libc::setgroups(len, login.groups.as_ptr()) },
+            crate::Operation::SetGroups,
+        ),
+        (unsafe { libc::setgid(login.gid) }, crate::Operation::SetGid),
+        (unsafe { libc::setuid(login.uid) }, crate::Operation::SetUid),
+    ] {
+        if res == -1 {
+            reporter.failed(operation);
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+// asks for the calling process to be sent signal when its parent exits. the
+// parent may have already exited before this runs, in which case the signal
+// would never be sent, so it is sent immediately instead. this runs in the
+// child (see State::run).
+#[cfg(target_os = "linux")]
+fn set_parent_death_signal(
+    signal: i32,
+    parent: rustix::process::Pid,
+) -> std::io::Result<()> {
+    let arg = libc::c_ulong::try_from(signal)
+        .map_err(|_| std::io::Error::from_raw_os_error(libc::EINVAL))?;
+    if unsafe { libc::prctl(libc::PR_SET_PDEATHSIG, arg) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    if rustix::process::getppid() != Some(parent) {
+        let pid = rustix::process::getpid().as_raw_nonzero().get();
+        if unsafe { libc::kill(pid, signal) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // the signal may be blocked or ignored, but the parent is gone
+        // either way
+        return Err(std::io::Error::from_raw_os_error(libc::ESRCH));
+    }
+    Ok(())
+}
+
+// creates a pipe with both ends close-on-exec. pipe2 isn't available
+// everywhere, so the flags are set separately.
+pub fn pipe() -> crate::Result<(std::os::fd::OwnedFd, std::os::fd::OwnedFd)> {
+    let (read, write) = rustix::pipe::pipe()?;
+    for fd in [&read, &write] {
+        let mut flags = rustix::io::fcntl_getfd(fd)?;
+        flags |= rustix::io::FdFlags::CLOEXEC;
+        rustix::io::fcntl_setfd(fd, flags)?;
+    }
+    Ok((read, write))
+}
+
+#[derive(Clone, Copy)]
+struct Reporter(std::os::fd::RawFd);
+
+impl Reporter {
+    // this runs in the child after forking (see State::run)
+    fn failed(self, operation: crate::Operation) {
+        let pid = rustix::process::getpid().as_raw_nonzero().get();
+        let mut buf = [0; 5];
+        buf[0] = operation.to_code();
+        buf[1..].copy_from_slice(&pid.to_ne_bytes());
+        // Safety: the fd is owned by the Spawn which is being run
+        let _ = rustix::io::write(
+            unsafe { std::os::fd::BorrowedFd::borrow_raw(self.0) },
+            &buf,
+        );
+    }
+}
+
+// makes pgid the foreground process group of the terminal. SIGTTOU is
+// blocked while doing this, since otherwise it would be sent to the calling
+// process if it isn't in the foreground process group itself. this is
+// called both in the parent and in the child (see State::run), and only
+// makes async-signal-safe calls.
+pub fn set_foreground(
+    fd: std::os::fd::BorrowedFd<'_>,
+    pgid: rustix::process::Pid,
+) -> rustix::io::Result<()> {
+    // Safety: the signal sets are initialized by sigemptyset before use
+    unsafe {
+        let mut block = std::mem::MaybeUninit::<libc::sigset_t>::uninit();
+        let mut old = std::mem::MaybeUninit::<libc::sigset_t>::uninit();
+        libc::sigemptyset(block.as_mut_ptr());
+        libc::sigemptyset(old.as_mut_ptr());
+        libc::sigaddset(block.as_mut_ptr(), libc::SIGTTOU);
+        libc::pthread_sigmask(
+            libc::SIG_BLOCK,
+            block.as_ptr(),
+            old.as_mut_ptr(),
+        );
+        let res = rustix::termios::tcsetpgrp(fd, pgid);
+        libc::pthread_sigmask(
+            libc::SIG_SETMASK,
+            old.as_ptr(),
+            std::ptr::null_mut(),
+        );
+        res
+    }
+}
+
+// makes pts the controlling terminal of the calling process, first making
+// the calling process the leader of a new session if it isn't already one
+// (see blocking::JobControl::new). returns the process group of the calling
+// process.
+pub fn take_controlling_terminal(
+    pts: &crate::sys::Pts,
+) -> crate::Result<rustix::process::Pid> {
+    let pid = rustix::process::getpid();
+    if rustix::process::getsid(None).ok() != Some(pid) {
+        rustix::process::setsid().map_err(|e| {
+            crate::Error::operation_failed(
+                crate::Operation::Setsid,
+                Some(pts.name()),
+                None,
+                e,
+            )
+        })?;
+    }
+    rustix::process::ioctl_tiocsctty(pts).map_err(|e| {
+        crate::Error::operation_failed(
+            crate::Operation::SetControllingTerminal,
+            Some(pts.name()),
+            None,
+            e,
+        )
+    })?;
+    Ok(rustix::process::getpgrp())
+}
+
+// set_foreground, for job control in the parent
+pub fn set_job_foreground(
+    pts: &crate::sys::Pts,
+    pgid: rustix::process::Pid,
+) -> crate::Result<()> {
+    set_foreground(std::os::fd::AsFd::as_fd(pts), pgid).map_err(|e| {
+        crate::Error::operation_failed(
+            crate::Operation::SetForeground,
+            Some(pts.name()),
+            Some(pgid.as_raw_nonzero().get().unsigned_abs()),
+            e,
+        )
+    })
+}
@@ -1,10 +1,22 @@
 /// Error type for errors from this crate
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
-    /// error came from std::io::Error
+    /// error came from [`std::io::Error`]
     Io(std::io::Error),
-    /// error came from nix::Error
+    /// error came from [`rustix::io::Errno`]
     Rustix(rustix::io::Errno),
+    /// a specific operation on the pty or the child process failed
+    Operation {
+        /// the operation which failed
+        operation: Operation,
+        /// the path of the child end of the pty, if known
+        pty: Option<std::path::PathBuf>,
+        /// the pid of the child process, if known
+        pid: Option<u32>,
+        /// the underlying error
+        source: std::io::Error,
+    },
     /// unsplit was called on halves of two different ptys
     #[cfg(feature = "async")]
     Unsplit(crate::OwnedReadPty, crate::OwnedWritePty),
@@ -15,14 +27,209 @@ impl std::fmt::Display for Error {
         match self {
             Self::Io(e) => write!(f, "{e}"),
             Self::Rustix(e) => write!(f, "{e}"),
-            #[cfg(feature = "async")]
-            Self::Unsplit(..) => {
-                write!(f, "unsplit called on halves of two different ptys")
+            Self::Operation {
+                operation,
+                pty,
+                pid,
+                source,
+            } => {
+                write!(f, "{operation} failed")?;
+                match (pty, pid) {
+                    (Some(pty), Some(pid)) => {
+                        write!(f, " (pty {}, pid {pid})", pty.display())?;
+                    }
+                    (Some(pty), None) => {
+                        write!(f, " (pty {})", pty.display())?;
+                    }
+                    (None, Some(pid)) => write!(f, " (pid {pid})")?,
+                    (None, None) => {}
+                }
+                write!(f, ": {source}")
             }
+            #[cfg(feature = "async")]
+            Self::Unsplit(..) => f.write_str(UNSPLIT_MESSAGE),
         }
     }
 }
 
+impl Error {
+    /// Returns the operation which failed, if known.
+    #[must_use]
+    pub fn operation(&self) -> Option<Operation> {
+        match self {
+            Self::Operation { operation, .. } => Some(*operation),
+            _ => None,
+        }
+    }
+
+    /// Returns the path of the child end of the pty involved in the failed
+    /// operation, if known.
+    #[must_use]
+    pub fn pty(&self) -> Option<&std::path::Path> {
+        match self {
+            Self::Operation { pty, .. } => pty.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the pid of the child process involved in the failed
+    /// operation, if known.
+    #[must_use]
+    pub fn pid(&self) -> Option<u32> {
+        match self {
+            Self::Operation { pid, .. } => *pid,
+            _ => None,
+        }
+    }
+
+    pub(crate) fn operation_failed(
+        operation: Operation,
+        pty: Option<&std::path::Path>,
+        pid: Option<u32>,
+        source: impl Into<std::io::Error>,
+    ) -> Self {
+        let source = source.into();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            %operation,
+            pty = pty.map(|pty| tracing::field::display(pty.display())),
+            pid,
+            error = %source,
+            "operation failed"
+        );
+        Self::Operation {
+            operation,
+            pty: pty.map(std::path::Path::to_path_buf),
+            pid,
+            source,
+        }
+    }
+}
+
+/// The operation which failed, for [`Error::Operation`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Operation {
+    /// allocating the pty via `posix_openpt`
+    Openpt,
+    /// granting access to the child end of the pty via `grantpt`
+    Grantpt,
+    /// unlocking the child end of the pty via `unlockpt`
+    Unlockpt,
+    /// finding the path of the child end of the pty via `ptsname`
+    Ptsname,
+    /// opening the child end of the pty
+    OpenPts,
+    /// setting the terminal size via `TIOCSWINSZ`
+    Resize,
+    /// creating a new session in the child via `setsid`
+    Setsid,
+    /// making the pty the controlling terminal of the child via
+    /// `TIOCSCTTY`
+    SetControllingTerminal,
+    /// creating a new process group in the child via `setpgid`
+    SetProcessGroup,
+    /// making a process group the foreground process group of the pty via
+    /// `tcsetpgrp`
+    SetForeground,
+    /// asking for the child to be sent a signal when its parent exits, via
+    /// `prctl(PR_SET_PDEATHSIG)`
+    ParentDeathSignal,
+    /// setting a resource limit for the child via `setrlimit`
+    SetResourceLimit,
+    /// changing the niceness of the child via `nice`
+    Nice,
+    /// setting the I/O priority of the child via `ioprio_set`
+    SetIoPriority,
+    /// looking up the user passed to `Command::as_user`
+    LookupUser,
+    /// giving ownership of the pts to the user passed to `Command::as_user`
+    ChownPts,
+    /// setting the supplementary groups of the child via `setgroups`
+    SetGroups,
+    /// setting the group id of the child via `setgid`
+    SetGid,
+    /// setting the user id of the child via `setuid`
+    SetUid,
+    /// mapping a file descriptor passed to `Command::fd_map` into the
+    /// child via `dup2`
+    FdMap,
+    /// running the hook passed to `Command::pre_exec` in the child
+    PreExec,
+    /// spawning the child process (including executing the program)
+    Spawn,
+}
+
+impl Operation {
+    // every operation, in order of the codes used to report the failing
+    // operation from the child after fork (0 is never used)
+    const CODES: [Self; 22] = [
+        Self::Openpt,
+        Self::Grantpt,
+        Self::Unlockpt,
+        Self::Ptsname,
+        Self::OpenPts,
+        Self::Resize,
+        Self::Setsid,
+        Self::SetControllingTerminal,
+        Self::PreExec,
+        Self::Spawn,
+        Self::FdMap,
+        Self::SetProcessGroup,
+        Self::SetForeground,
+        Self::ParentDeathSignal,
+        Self::SetResourceLimit,
+        Self::Nice,
+        Self::SetIoPriority,
+        Self::LookupUser,
+        Self::ChownPts,
+        Self::SetGroups,
+        Self::SetGid,
+        Self::SetUid,
+    ];
+
+    pub(crate) fn to_code(self) -> u8 {
+        Self::CODES
+            .iter()
+            .position(|&operation| operation == self)
+            .and_then(|i| u8::try_from(i + 1).ok())
+            .unwrap_or(0)
+    }
+
+    pub(crate) fn from_code(code: u8) -> Option<Self> {
+        Self::CODES.get(usize::from(code).checked_sub(1)?).copied()
+    }
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Openpt => "openpt",
+            Self::Grantpt => "grantpt",
+            Self::Unlockpt => "unlockpt",
+            Self::Ptsname => "ptsname",
+            Self::OpenPts => "opening pts",
+            Self::Resize => "TIOCSWINSZ",
+            Self::Setsid => "setsid",
+            Self::SetControllingTerminal => "TIOCSCTTY",
+            Self::SetProcessGroup => "setpgid",
+            Self::SetForeground => "tcsetpgrp",
+            Self::ParentDeathSignal => "PR_SET_PDEATHSIG",
+            Self::SetResourceLimit => "setrlimit",
+            Self::Nice => "nice",
+            Self::SetIoPriority => "ioprio_set",
+            Self::LookupUser => "looking up user",
+            Self::ChownPts => "chown pts",
+            Self::SetGroups => "setgroups",
+            Self::SetGid => "setgid",
+            Self::SetUid => "setuid",
+            Self::FdMap => "dup2",
+            Self::PreExec => "pre_exec",
+            Self::Spawn => "spawn",
+        })
+    }
+}
+
 impl std::convert::From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Self {
         Self::Io(e)
@@ -40,11 +247,34 @@ impl std::error::Error for Error {
         match self {
             Self::Io(e) => Some(e),
             Self::Rustix(e) => Some(e),
+            Self::Operation { source, .. } => Some(source),
             #[cfg(feature = "async")]
             Self::Unsplit(..) => None,
         }
     }
 }
 
+const UNSPLIT_MESSAGE: &str =
+    "unsplit called on halves of two different ptys";
+
+/// Error returned by `unsplit` when the two halves came from different pty
+/// instances, for the pty types other than [`Pty`](crate::Pty) (which uses
+/// [`Error::Unsplit`]). Contains the mismatched halves.
+pub struct UnsplitError<R, W>(pub R, pub W);
+
+impl<R, W> std::fmt::Debug for UnsplitError<R, W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("UnsplitError").finish_non_exhaustive()
+    }
+}
+
+impl<R, W> std::fmt::Display for UnsplitError<R, W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(UNSPLIT_MESSAGE)
+    }
+}
+
+impl<R, W> std::error::Error for UnsplitError<R, W> {}
+
 /// Convenience wrapper for `Result`s using [`Error`](Error)
 pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,152 @@
+/// An async equivalent of [`blocking::JobControl`](crate::blocking::JobControl)
+///
+/// Note that this changes the session and controlling terminal of the
+/// entire calling process (see [`new`](Self::new)).
+pub struct JobControl {
+    pts: crate::Pts,
+    pgid: rustix::process::Pid,
+}
+
+impl JobControl {
+    /// Makes `pts` the controlling terminal of the calling process, first
+    /// making the calling process the leader of a new session if it isn't
+    /// already a session leader. This affects the entire calling process in
+    /// the same ways as
+    /// [`blocking::JobControl::new`](crate::blocking::JobControl::new).
+    ///
+    /// # Errors
+    /// Returns an error with [`Operation::Setsid`](crate::Operation::Setsid)
+    /// (`EPERM`) if the calling process is a process group leader but not a
+    /// session leader, or an error with
+    /// [`Operation::SetControllingTerminal`](crate::Operation::SetControllingTerminal)
+    /// if the pty can't be made the controlling terminal of the calling
+    /// process.
+    pub fn new(pts: crate::Pts) -> crate::Result<Self> {
+        let pgid = crate::child::take_controlling_terminal(&pts.0)?;
+        Ok(Self { pts, pgid })
+    }
+
+    /// Spawns `pipeline` on the pty as a single job, in a new process group
+    /// (see [`Pipeline::spawn`](crate::Pipeline::spawn)). If `foreground` is
+    /// true, the job will be made the foreground job of the pty. If any of
+    /// the commands fails to spawn, the processes which were already spawned
+    /// for the job are killed. The processes are spawned outside of tokio's
+    /// child process handling, and can only be waited for via
+    /// [`wait`](Self::wait).
+    ///
+    /// # Errors
+    /// Returns an error if `pipeline` is empty, if any of the commands fails
+    /// to spawn (see [`Command::spawn`](crate::Command::spawn)), or if the
+    /// job couldn't be made the foreground job.
+    pub fn spawn(
+        &self,
+        pipeline: &mut crate::Pipeline,
+        foreground: bool,
+    ) -> crate::Result<crate::Job> {
+        let children = pipeline.spawn_job(&self.pts, foreground)?;
+        let job = crate::Job::new(
+            children
+                .iter()
+                .map(rustix::process::Pid::from_child)
+                .collect(),
+            foreground,
+        );
+        if foreground {
+            // the children do this too, but they may not have run yet
+            crate::child::set_job_foreground(&self.pts.0, job.pgid)?;
+        }
+        Ok(job)
+    }
+
+    /// Waits until every process in `job` has exited, or until any process
+    /// in `job` is stopped. If `job` was in the foreground, the calling
+    /// process is made the foreground process group again afterwards (as
+    /// a shell does when a job exits or is suspended).
+    ///
+    /// This is cancel safe, in that no change to the state of the job is
+    /// lost if the future is dropped, but the foreground process group is
+    /// only changed once the future completes.
+    ///
+    /// # Errors
+    /// Returns an error if waiting for the processes fails, or if the
+    /// calling process couldn't be made the foreground process group again.
+    pub async fn wait(
+        &self,
+        job: &mut crate::Job,
+    ) -> crate::Result<crate::JobStatus> {
+        let res = wait_for_change(job).await;
+        self.take_foreground(job)?;
+        res
+    }
+
+    /// Makes `job` the foreground job of the pty, and continues it if it
+    /// was stopped.
+    ///
+    /// # Errors
+    /// Returns an error if the job could not be made the foreground job, or
+    /// if sending `SIGCONT` to it fails.
+    pub fn foreground(&self, job: &mut crate::Job) -> crate::Result<()> {
+        crate::child::set_job_foreground(&self.pts.0, job.pgid)?;
+        job.foreground = true;
+        Ok(rustix::process::kill_process_group(
+            job.pgid,
+            rustix::process::Signal::Cont,
+        )?)
+    }
+
+    /// Continues `job` in the background, if it was stopped. If it was the
+    /// foreground job, the calling process is made the foreground process
+    /// group instead.
+    ///
+    /// # Errors
+    /// Returns an error if the calling process could not be made the
+    /// foreground process group, or if sending `SIGCONT` to the job fails.
+    pub fn background(&self, job: &mut crate::Job) -> crate::Result<()> {
+        self.take_foreground(job)?;
+        Ok(rustix::process::kill_process_group(
+            job.pgid,
+            rustix::process::Signal::Cont,
+        )?)
+    }
+
+    /// Returns the process group id of the current foreground process group
+    /// of the pty.
+    ///
+    /// # Errors
+    /// Returns an error if the foreground process group can't be retrieved.
+    pub fn foreground_pgid(&self) -> crate::Result<u32> {
+        Ok(rustix::termios::tcgetpgrp(&self.pts.0)?
+            .as_raw_nonzero()
+            .get()
+            .unsigned_abs())
+    }
+
+    fn take_foreground(&self, job: &mut crate::Job) -> crate::Result<()> {
+        if job.foreground {
+            crate::child::set_job_foreground(&self.pts.0, self.pgid)?;
+            job.foreground = false;
+        }
+        Ok(())
+    }
+}
+
+async fn wait_for_change(
+    job: &mut crate::Job,
+) -> crate::Result<crate::JobStatus> {
+    // listen for SIGCHLD before checking the job, so that a change between
+    // the check and the wait isn't missed
+    let mut sigchld = tokio::signal::unix::signal(
+        tokio::signal::unix::SignalKind::child(),
+    )?;
+    loop {
+        if let Some(status) = job.try_wait_for_change()? {
+            return Ok(status);
+        }
+        if sigchld.recv().await.is_none() {
+            return Err(std::io::Error::other(
+                "tokio runtime is shutting down",
+            )
+            .into());
+        }
+    }
+}
@@ -39,7 +39,26 @@
 //! # Features
 //!
 //! By default, only the [`blocking`](crate::blocking) APIs are available. To
-//! include the asynchronous APIs, you must enable the `async` feature.
+//! include the asynchronous APIs, you must enable the `async` feature. To
+//! use the asynchronous APIs with `async-io` based runtimes (such as `smol`)
+//! rather than tokio, enable the `async-io` feature, which provides the
+//! [`async_io`](crate::async_io) module.
+//!
+//! To register a [`blocking::Pty`](crate::blocking::Pty) with a custom `mio`
+//! event loop, enable the `mio` feature, which implements
+//! `mio::event::Source` for it and for its owned halves.
+//!
+//! On Linux, the `io-uring` feature provides the [`uring`](crate::uring)
+//! module, which performs pty reads and writes via `io_uring` using
+//! `tokio-uring`.
+//!
+//! The `codec` feature adds conversions from the async pty types into
+//! `Stream`s and `Sink`s, as well as support for `tokio_util::codec`.
+//!
+//! The `tracing` feature emits `tracing` events for pty allocation, opening
+//! the pts, spawning, resizing, and reading and writing (at the `trace`
+//! level), including the name of the pts and the pid of the child where
+//! relevant.
 
 #![warn(clippy::cargo)]
 #![warn(clippy::pedantic)]
@@ -48,18 +67,31 @@
 #![warn(clippy::get_unwrap)]
 #![allow(clippy::cognitive_complexity)]
 #![allow(clippy::missing_const_for_fn)]
+#![allow(clippy::multiple_crate_versions)]
 #![allow(clippy::similar_names)]
 #![allow(clippy::struct_excessive_bools)]
 #![allow(clippy::too_many_arguments)]
 #![allow(clippy::too_many_lines)]
 #![allow(clippy::type_complexity)]
 
+mod child;
 mod error;
-pub use error::{Error, Result};
+pub use error::{Error, Operation, Result, UnsplitError};
 mod types;
-pub use types::Size;
+#[cfg(target_os = "linux")]
+pub use types::IoPriority;
+pub use types::{Resource, SessionMode, Size, User};
 
+mod login;
+mod metrics;
+pub use metrics::{Metrics, MetricsHandle};
+#[cfg(target_os = "linux")]
+mod pidfd;
+#[cfg(all(feature = "async", target_os = "linux"))]
+pub use pidfd::Pidfd;
 mod sys;
+mod tap;
+pub use tap::{Chunk, Tap, TapId};
 
 pub mod blocking;
 
@@ -68,6 +100,22 @@ mod command;
 #[cfg(feature = "async")]
 pub use command::Command;
 #[cfg(feature = "async")]
+mod job;
+#[cfg(feature = "async")]
+pub use blocking::{Job, JobStatus};
+#[cfg(feature = "async")]
+pub use job::JobControl;
+#[cfg(feature = "async")]
+mod pipeline;
+#[cfg(feature = "async")]
+pub use pipeline::Pipeline;
+#[cfg(feature = "async")]
 mod pty;
 #[cfg(feature = "async")]
 pub use pty::{OwnedReadPty, OwnedWritePty, Pts, Pty, ReadPty, WritePty};
+
+#[cfg(feature = "async-io")]
+pub mod async_io;
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+pub mod uring;
@@ -0,0 +1,288 @@
+// looking up users and groups for Command::as_user. none of this is
+// async-signal-safe, so it all happens in the parent before spawning, and
+// the child only makes the resulting setgroups/setgid/setuid calls.
+
+// the resolved credentials which the child switches to
+#[derive(Clone)]
+pub struct Login {
+    pub uid: libc::uid_t,
+    pub gid: libc::gid_t,
+    pub groups: Vec<libc::gid_t>,
+}
+
+pub struct Passwd {
+    pub name: std::ffi::OsString,
+    pub uid: libc::uid_t,
+    pub gid: libc::gid_t,
+    pub dir: std::ffi::OsString,
+    pub shell: std::ffi::OsString,
+}
+
+impl Passwd {
+    pub fn lookup(user: &crate::User) -> std::io::Result<Self> {
+        match user {
+            crate::User::Name(name) => {
+                let name = std::ffi::CString::new(
+                    std::os::unix::ffi::OsStrExt::as_bytes(name.as_os_str()),
+                )
+                .map_err(|_| {
+                    std::io::Error::from(std::io::ErrorKind::InvalidInput)
+                })?;
+                Self::getpw(|pwd, buf, len, result| unsafe {
+                    libc::getpwnam_r(name.as_ptr(), pwd, buf, len, result)
+                })
+            }
+            crate::User::Id(uid) => {
+                Self::getpw(|pwd, buf, len, result| unsafe {
+                    libc::getpwuid_r(*uid, pwd, buf, len, result)
+                })
+            }
+        }
+    }
+
+    // calls getpwnam_r or getpwuid_r, growing the buffer as needed
+    fn getpw(
+        f: impl Fn(
+            *mut libc::passwd,
+            *mut libc::c_char,
+            usize,
+            *mut *mut libc::passwd,
+        ) -> libc::c_int,
+    ) -> std::io::Result<Self> {
+        let mut buf = vec![0; 1024];
+        loop {
+            let mut pwd = unsafe { std::mem::zeroed::<libc::passwd>() };
+            let mut result = std::ptr::null_mut();
+            let err =
+                f(&raw mut pwd, buf.as_mut_ptr(), buf.len(), &raw mut result);
+            if err == libc::ERANGE {
+                buf.resize(buf.len() * 2, 0);
+                continue;
+            }
+            if err != 0 {
+                return Err(std::io::Error::from_raw_os_error(err));
+            }
+            if result.is_null() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "no such user",
+                ));
+            }
+            // Safety: the pointers are either null or point to nul
+            // terminated strings stored in buf
+            let string = |ptr: *const libc::c_char| {
+                if ptr.is_null() {
+                    return std::ffi::OsString::new();
+                }
+                <std::ffi::OsStr as std::os::unix::ffi::OsStrExt>::from_bytes(
+                    unsafe { std::ffi::CStr::from_ptr(ptr) }.to_bytes(),
+                )
+                .to_os_string()
+            };
+            return Ok(Self {
+                name: string(pwd.pw_name),
+                uid: pwd.pw_uid,
+                gid: pwd.pw_gid,
+                dir: string(pwd.pw_dir),
+                shell: string(pwd.pw_shell),
+            });
+        }
+    }
+
+    // the user's primary group and every group which lists the user as a
+    // member, as initgroups would set
+    pub fn groups(&self) -> std::io::Result<Vec<libc::gid_t>> {
+        let name = std::ffi::CString::new(
+            std::os::unix::ffi::OsStrExt::as_bytes(self.name.as_os_str()),
+        )
+        .map_err(|_| {
+            std::io::Error::from(std::io::ErrorKind::InvalidInput)
+        })?;
+        let mut groups: Vec<libc::gid_t> = vec![0; 32];
+        loop {
+            let mut len =
+                libc::c_int::try_from(groups.len()).map_err(|_| {
+                    std::io::Error::from_raw_os_error(libc::EINVAL)
+                })?;
+            #[cfg(not(target_vendor = "apple"))]
+            let res = unsafe {
+                libc::getgrouplist(
+                    name.as_ptr(),
+                    self.gid,
+                    groups.as_mut_ptr(),
+                    &raw mut len,
+                )
+            };
+            // macos uses ints for group ids here, which have the same
+            // representation
+            #[cfg(target_vendor = "apple")]
+            let res = unsafe {
+                libc::getgrouplist(
+                    name.as_ptr(),
+                    self.gid.cast_signed(),
+                    groups.as_mut_ptr().cast(),
+                    &raw mut len,
+                )
+            };
+            let len = usize::try_from(len).unwrap_or_default();
+            if res == -1 {
+                // len is set to the required size
+                groups.resize(len.max(groups.len() * 2), 0);
+                continue;
+            }
+            groups.truncate(len);
+            return Ok(groups);
+        }
+    }
+
+    pub fn login(&self) -> std::io::Result<Login> {
+        Ok(Login {
+            uid: self.uid,
+            gid: self.gid,
+            groups: self.groups()?,
+        })
+    }
+
+    // an empty shell field means /bin/sh, as with login
+    fn shell(&self) -> std::ffi::OsString {
+        if self.shell.is_empty() {
+            "/bin/sh".into()
+        } else {
+            self.shell.clone()
+        }
+    }
+
+    pub fn env(&self) -> [(&'static str, std::ffi::OsString); 4] {
+        [
+            ("HOME", self.dir.clone()),
+            ("SHELL", self.shell()),
+            ("USER", self.name.clone()),
+            ("LOGNAME", self.name.clone()),
+        ]
+    }
+}
+
+// how to run a user's login shell, for Command::login_shell
+pub struct Shell {
+    pub program: std::ffi::OsString,
+    // the shell's name prefixed with a dash, which tells it to act as a
+    // login shell
+    pub arg0: std::ffi::OsString,
+    pub dir: std::ffi::OsString,
+    // None if the user is already the current user
+    pub user: Option<crate::User>,
+    pub env: Vec<(&'static str, std::ffi::OsString)>,
+}
+
+impl Shell {
+    pub fn new(user: crate::User) -> crate::Result<Self> {
+        let passwd = Passwd::lookup(&user).map_err(|e| {
+            crate::Error::operation_failed(
+                crate::Operation::LookupUser,
+                None,
+                None,
+                e,
+            )
+        })?;
+        let program = passwd.shell();
+        let mut arg0 = std::ffi::OsString::from("-");
+        arg0.push(
+            std::path::Path::new(&program)
+                .file_name()
+                .unwrap_or(&program),
+        );
+        let mut env = vec![("TERM", "xterm-256color".into())];
+        // switching users would fail if the calling process isn't
+        // privileged, even when it is already running as the user, and
+        // as_user sets the rest of the environment itself
+        let user = if passwd.uid == rustix::process::geteuid().as_raw() {
+            env.extend(passwd.env());
+            None
+        } else {
+            Some(user)
+        };
+        Ok(Self {
+            program,
+            arg0,
+            dir: passwd.dir,
+            user,
+            env,
+        })
+    }
+}
+
+// the id of the tty group, which login gives ownership of the terminal to
+pub fn tty_group() -> std::io::Result<Option<libc::gid_t>> {
+    let mut buf = vec![0; 1024];
+    loop {
+        let mut grp = unsafe { std::mem::zeroed::<libc::group>() };
+        let mut result = std::ptr::null_mut();
+        let err = unsafe {
+            libc::getgrnam_r(
+                c"tty".as_ptr(),
+                &raw mut grp,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &raw mut result,
+            )
+        };
+        if err == libc::ERANGE {
+            buf.resize(buf.len() * 2, 0);
+            continue;
+        }
+        if err != 0 {
+            return Err(std::io::Error::from_raw_os_error(err));
+        }
+        return Ok((!result.is_null()).then_some(grp.gr_gid));
+    }
+}
+
+// the owner and mode of the pts before it was given to a user, so that it
+// can be given back if spawning fails
+pub struct Owner {
+    uid: libc::uid_t,
+    gid: libc::gid_t,
+    mode: rustix::fs::RawMode,
+}
+
+impl Owner {
+    pub fn restore(&self, pts: &crate::sys::Pts) -> std::io::Result<()> {
+        fchown(pts, self.uid, self.gid)?;
+        rustix::fs::fchmod(pts, rustix::fs::Mode::from_raw_mode(self.mode))?;
+        Ok(())
+    }
+}
+
+// gives ownership of the pts to the user, as login does: owned by the
+// user and the tty group, and writable by the group (so that tools like
+// write(1) work), or only accessible by the user if there is no tty group.
+// returns the previous owner.
+pub fn chown_pts(
+    pts: &crate::sys::Pts,
+    login: &Login,
+) -> std::io::Result<Owner> {
+    let stat = rustix::fs::fstat(pts)?;
+    let owner = Owner {
+        uid: stat.st_uid,
+        gid: stat.st_gid,
+        mode: stat.st_mode & 0o7777,
+    };
+    let (gid, mode) =
+        tty_group()?.map_or((login.gid, 0o600), |gid| (gid, 0o620));
+    fchown(pts, login.uid, gid)?;
+    rustix::fs::fchmod(pts, rustix::fs::Mode::from_raw_mode(mode))?;
+    Ok(owner)
+}
+
+fn fchown(
+    pts: &crate::sys::Pts,
+    uid: libc::uid_t,
+    gid: libc::gid_t,
+) -> std::io::Result<()> {
+    if unsafe { libc::fchown(std::os::fd::AsRawFd::as_raw_fd(pts), uid, gid) }
+        == -1
+    {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
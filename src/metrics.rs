@@ -0,0 +1,208 @@
+/// A snapshot of the metrics collected for a pty, returned by
+/// [`MetricsHandle::snapshot`]
+#[derive(Debug, Clone, Copy)]
+pub struct Metrics {
+    bytes_read: u64,
+    bytes_written: u64,
+    reads: u64,
+    writes: u64,
+    largest_read: u64,
+    last_read: Option<std::time::Instant>,
+    write_stall: std::time::Duration,
+    taken: std::time::Instant,
+}
+
+impl Metrics {
+    /// Returns the total number of bytes read from the pty (the output of
+    /// the child process).
+    #[must_use]
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Returns the total number of bytes written to the pty (the input to
+    /// the child process).
+    #[must_use]
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Returns the number of reads from the pty which returned data or end
+    /// of file.
+    #[must_use]
+    pub fn reads(&self) -> u64 {
+        self.reads
+    }
+
+    /// Returns the number of writes to the pty which succeeded.
+    #[must_use]
+    pub fn writes(&self) -> u64 {
+        self.writes
+    }
+
+    /// Returns the largest amount of data returned by a single read from
+    /// the pty.
+    #[must_use]
+    pub fn largest_read(&self) -> u64 {
+        self.largest_read
+    }
+
+    /// Returns the time at which data was last read from the pty, or `None`
+    /// if no data has been read yet.
+    #[must_use]
+    pub fn last_read(&self) -> Option<std::time::Instant> {
+        self.last_read
+    }
+
+    /// Returns the time between the last read of data from the pty and when
+    /// this snapshot was taken, or `None` if no data has been read yet.
+    #[must_use]
+    pub fn since_last_read(&self) -> Option<std::time::Duration> {
+        self.last_read.map(|last| self.taken.duration_since(last))
+    }
+
+    /// Returns the total time that writes to the pty have spent waiting
+    /// for space in the pty's buffer (because the child process wasn't
+    /// reading its input quickly enough). This is measured from when a
+    /// write first found the buffer to be full until the next successful
+    /// write, and includes a stall which is still ongoing.
+    #[must_use]
+    pub fn write_stall(&self) -> std::time::Duration {
+        self.write_stall
+    }
+}
+
+/// A handle to the metrics collected for a pty
+///
+/// Returned by `enable_metrics` (for instance,
+/// [`blocking::Pty::enable_metrics`](crate::blocking::Pty::enable_metrics)).
+/// It can be cloned, and remains usable after the pty is split or dropped.
+#[derive(Debug, Clone)]
+pub struct MetricsHandle(pub(crate) std::sync::Arc<Collector>);
+
+impl MetricsHandle {
+    /// Returns a snapshot of the metrics collected so far.
+    #[must_use]
+    pub fn snapshot(&self) -> Metrics {
+        self.0.snapshot()
+    }
+}
+
+// times are stored as nanoseconds since the collector was created, offset
+// by one so that zero can mean "never"
+#[derive(Debug)]
+pub struct Collector {
+    created: std::time::Instant,
+    bytes_read: std::sync::atomic::AtomicU64,
+    bytes_written: std::sync::atomic::AtomicU64,
+    reads: std::sync::atomic::AtomicU64,
+    writes: std::sync::atomic::AtomicU64,
+    largest_read: std::sync::atomic::AtomicU64,
+    last_read: std::sync::atomic::AtomicU64,
+    stall_started: std::sync::atomic::AtomicU64,
+    write_stall: std::sync::atomic::AtomicU64,
+}
+
+impl Default for Collector {
+    fn default() -> Self {
+        Self {
+            created: std::time::Instant::now(),
+            bytes_read: std::sync::atomic::AtomicU64::new(0),
+            bytes_written: std::sync::atomic::AtomicU64::new(0),
+            reads: std::sync::atomic::AtomicU64::new(0),
+            writes: std::sync::atomic::AtomicU64::new(0),
+            largest_read: std::sync::atomic::AtomicU64::new(0),
+            last_read: std::sync::atomic::AtomicU64::new(0),
+            stall_started: std::sync::atomic::AtomicU64::new(0),
+            write_stall: std::sync::atomic::AtomicU64::new(0),
+        }
+    }
+}
+
+impl Collector {
+    pub fn read(&self, bytes: usize) {
+        let bytes = to_u64(bytes);
+        self.reads
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        if bytes > 0 {
+            self.bytes_read
+                .fetch_add(bytes, std::sync::atomic::Ordering::Relaxed);
+            self.largest_read
+                .fetch_max(bytes, std::sync::atomic::Ordering::Relaxed);
+            self.last_read
+                .store(self.now(), std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    pub fn write(&self, bytes: usize) {
+        self.writes
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.bytes_written
+            .fetch_add(to_u64(bytes), std::sync::atomic::Ordering::Relaxed);
+        let started = self
+            .stall_started
+            .swap(0, std::sync::atomic::Ordering::Relaxed);
+        if started != 0 {
+            self.write_stall.fetch_add(
+                self.now().saturating_sub(started),
+                std::sync::atomic::Ordering::Relaxed,
+            );
+        }
+    }
+
+    pub fn stall(&self) {
+        // only the first write to find the buffer full starts the stall
+        let _ = self.stall_started.compare_exchange(
+            0,
+            self.now(),
+            std::sync::atomic::Ordering::Relaxed,
+            std::sync::atomic::Ordering::Relaxed,
+        );
+    }
+
+    pub fn snapshot(&self) -> Metrics {
+        let now = self.now();
+        let started = self
+            .stall_started
+            .load(std::sync::atomic::Ordering::Relaxed);
+        let ongoing = if started == 0 {
+            0
+        } else {
+            now.saturating_sub(started)
+        };
+        let last_read =
+            self.last_read.load(std::sync::atomic::Ordering::Relaxed);
+        Metrics {
+            bytes_read: self
+                .bytes_read
+                .load(std::sync::atomic::Ordering::Relaxed),
+            bytes_written: self
+                .bytes_written
+                .load(std::sync::atomic::Ordering::Relaxed),
+            reads: self.reads.load(std::sync::atomic::Ordering::Relaxed),
+            writes: self.writes.load(std::sync::atomic::Ordering::Relaxed),
+            largest_read: self
+                .largest_read
+                .load(std::sync::atomic::Ordering::Relaxed),
+            last_read: (last_read != 0).then(|| self.instant(last_read)),
+            write_stall: std::time::Duration::from_nanos(
+                self.write_stall
+                    .load(std::sync::atomic::Ordering::Relaxed)
+                    .saturating_add(ongoing),
+            ),
+            taken: self.instant(now),
+        }
+    }
+
+    fn now(&self) -> u64 {
+        to_u64(self.created.elapsed().as_nanos()).saturating_add(1)
+    }
+
+    fn instant(&self, time: u64) -> std::time::Instant {
+        self.created + std::time::Duration::from_nanos(time - 1)
+    }
+}
+
+fn to_u64<T: TryInto<u64>>(n: T) -> u64 {
+    n.try_into().unwrap_or(u64::MAX)
+}
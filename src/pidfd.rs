@@ -0,0 +1,144 @@
+// pidfd operations shared by blocking::Pidfd and Pidfd. a pidfd refers to a
+// specific process rather than to a pid, so signalling or waiting via one
+// can't accidentally affect an unrelated process which reused the pid.
+
+// opening the pidfd for a child which has been spawned but not waited for
+// is race free, since the child's pid can't be reused until it is reaped
+pub fn open(
+    child: &std::process::Child,
+) -> crate::Result<std::os::fd::OwnedFd> {
+    let pid = rustix::process::Pid::from_child(child);
+    Ok(rustix::process::pidfd_open(
+        pid,
+        rustix::process::PidfdFlags::empty(),
+    )?)
+}
+
+pub fn send_signal(
+    fd: std::os::fd::BorrowedFd<'_>,
+    signal: i32,
+) -> crate::Result<()> {
+    let signal =
+        rustix::process::Signal::from_raw(signal).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid signal",
+            )
+        })?;
+    Ok(rustix::process::pidfd_send_signal(fd, signal)?)
+}
+
+// reaps the process if it has exited (or waits for it to exit, if block is
+// true)
+pub fn wait(
+    fd: std::os::fd::BorrowedFd<'_>,
+    block: bool,
+) -> crate::Result<Option<std::process::ExitStatus>> {
+    use std::os::fd::AsRawFd as _;
+    use std::os::unix::process::ExitStatusExt as _;
+
+    let id = libc::id_t::try_from(fd.as_raw_fd())
+        .map_err(|_| std::io::Error::from_raw_os_error(libc::EBADF))?;
+    let mut flags = libc::WEXITED;
+    if !block {
+        flags |= libc::WNOHANG;
+    }
+    let info = loop {
+        let mut info = unsafe { std::mem::zeroed::<libc::siginfo_t>() };
+        if unsafe { libc::waitid(libc::P_PIDFD, id, &raw mut info, flags) }
+            == -1
+        {
+            let e = std::io::Error::last_os_error();
+            if e.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(e.into());
+        }
+        break info;
+    };
+    // with WNOHANG, waitid leaves the siginfo zeroed if the process hasn't
+    // exited yet
+    if unsafe { info.si_pid() } == 0 {
+        return Ok(None);
+    }
+    // convert back to the status format which ExitStatus expects
+    let status = unsafe { info.si_status() };
+    let raw = match info.si_code {
+        libc::CLD_EXITED => status << 8,
+        libc::CLD_DUMPED => status | 0x80,
+        _ => status,
+    };
+    Ok(Some(std::process::ExitStatus::from_raw(raw)))
+}
+
+/// A pidfd for a child process, which can be used to signal and wait for
+/// the child without any risk of the pid having been reused (Linux only)
+///
+/// Returned by [`Command::spawn_pidfd`](crate::Command::spawn_pidfd).
+/// Waiting is driven by readiness of the pidfd, rather than by tokio's
+/// `SIGCHLD` handling.
+#[cfg(feature = "async")]
+pub struct Pidfd(tokio::io::unix::AsyncFd<std::os::fd::OwnedFd>);
+
+#[cfg(feature = "async")]
+impl Pidfd {
+    pub(crate) fn open(child: &std::process::Child) -> crate::Result<Self> {
+        let fd = open(child)?;
+        // AsyncFd::with_interest is deprecated because it can't guarantee
+        // that the fd outlives the registration, but the AsyncFd owns the
+        // pidfd
+        #[allow(deprecated)]
+        Ok(Self(tokio::io::unix::AsyncFd::with_interest(
+            fd,
+            tokio::io::Interest::READABLE,
+        )?))
+    }
+
+    /// Sends `signal` to the child via `pidfd_send_signal`.
+    ///
+    /// # Errors
+    /// Returns an error if `signal` isn't a valid signal, or if sending the
+    /// signal fails.
+    pub fn send_signal(&self, signal: i32) -> crate::Result<()> {
+        send_signal(std::os::fd::AsFd::as_fd(self.0.get_ref()), signal)
+    }
+
+    /// Reaps the child if it has exited, without waiting.
+    ///
+    /// # Errors
+    /// Returns an error if the child has already been reaped.
+    pub fn try_wait(
+        &self,
+    ) -> crate::Result<Option<std::process::ExitStatus>> {
+        wait(std::os::fd::AsFd::as_fd(self.0.get_ref()), false)
+    }
+
+    /// Waits for the child to exit, and reaps it.
+    ///
+    /// # Errors
+    /// Returns an error if the child has already been reaped, or if polling
+    /// the pidfd fails.
+    pub async fn wait(&self) -> crate::Result<std::process::ExitStatus> {
+        loop {
+            let mut guard = self.0.readable().await?;
+            if let Some(status) = self.try_wait()? {
+                return Ok(status);
+            }
+            guard.clear_ready();
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl std::os::fd::AsFd for Pidfd {
+    fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        self.0.get_ref().as_fd()
+    }
+}
+
+#[cfg(feature = "async")]
+impl From<Pidfd> for std::os::fd::OwnedFd {
+    fn from(pidfd: Pidfd) -> Self {
+        pidfd.0.into_inner()
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(clippy::module_name_repetitions)]
 
-use std::io::{Read as _, Write as _};
+use std::io::Write as _;
+use std::os::fd::AsFd as _;
 
 type AsyncPty = tokio::io::unix::AsyncFd<crate::sys::Pty>;
 
@@ -15,7 +16,10 @@ impl Pty {
     /// unable to put it into non-blocking mode.
     pub fn new() -> crate::Result<Self> {
         let pty = crate::sys::Pty::open()?;
-        pty.set_nonblocking()?;
+        pty.set_nonblocking(true)?;
+        // AsyncFd::new is deprecated because it can't guarantee that the fd
+        // outlives the registration, but sys::Pty owns its fd
+        #[allow(deprecated)]
         Ok(Self(tokio::io::unix::AsyncFd::new(pty)?))
     }
 
@@ -27,6 +31,17 @@ impl Pty {
         self.0.get_ref().set_term_size(size)
     }
 
+    /// Controls whether shutting down the write side of the pty (via
+    /// [`tokio::io::AsyncWrite::poll_shutdown`]) sends the `VEOF` character
+    /// (usually `^D`) to the child, so that a process reading from the pty
+    /// sees end of file. If the last data written did not end a line, an
+    /// additional `VEOF` is sent first to submit the partial line. This only
+    /// has an effect while the pty is in canonical mode. Disabled by
+    /// default, in which case shutting down does nothing.
+    pub fn set_eof_on_shutdown(&self, enabled: bool) {
+        self.0.get_ref().set_eof_on_shutdown(enabled);
+    }
+
     /// Opens a file descriptor for the other end of the pty, which should be
     /// attached to the child process running in it. See
     /// [`Command::spawn`](crate::Command::spawn).
@@ -38,6 +53,41 @@ impl Pty {
         Ok(Pts(self.0.get_ref().pts()?))
     }
 
+    /// Controls whether reading from the pty after every process holding the
+    /// other end of the pty has closed it reports end of file (a read of
+    /// zero bytes) rather than an error. On Linux, this condition is
+    /// otherwise reported as `EIO`. Enabled by default.
+    pub fn set_hangup_as_eof(&self, enabled: bool) {
+        self.0.get_ref().set_hangup_as_eof(enabled);
+    }
+
+    /// Returns true if a read from the pty has observed that every process
+    /// holding the other end of the pty has closed it. This can be used to
+    /// tell a hangup apart from other errors or from a read of zero bytes.
+    #[must_use]
+    pub fn is_hung_up(&self) -> bool {
+        self.0.get_ref().is_hung_up()
+    }
+
+    /// Returns a future which resolves once every process holding the other
+    /// end of the pty has closed it, without reading any data from the pty.
+    /// The returned future does not borrow the pty, so it can be awaited
+    /// independently of any reads or writes, including after calling
+    /// [`into_split`](Self::into_split). Note that the [`Pts`] returned by
+    /// [`pts`](Self::pts) also holds the other end of the pty open, so it
+    /// should be dropped once the child has been spawned.
+    ///
+    /// # Errors
+    /// The returned future resolves to an error if we were unable to
+    /// duplicate the pty file descriptor or register it with the tokio
+    /// reactor.
+    pub fn hangup(
+        &self,
+    ) -> impl std::future::Future<Output = crate::Result<()>> + Send + 'static
+    {
+        hangup(self.0.get_ref())
+    }
+
     /// Splits a `Pty` into a read half and a write half, which can be used to
     /// read from and write to the pty concurrently. Does not allocate, but
     /// the returned halves cannot be moved to independent tasks.
@@ -57,6 +107,41 @@ impl Pty {
         let write_pt = std::sync::Arc::clone(&read_pt);
         (OwnedReadPty(read_pt), OwnedWritePty(write_pt))
     }
+
+    /// Wraps the pty in a [`tokio_util::codec::Framed`] using the given
+    /// codec, which provides a `Stream` of decoded frames and a `Sink` of
+    /// frames to encode.
+    #[cfg(feature = "codec")]
+    #[must_use]
+    pub fn framed<C>(self, codec: C) -> tokio_util::codec::Framed<Self, C> {
+        tokio_util::codec::Framed::new(self, codec)
+    }
+
+    /// Attaches a [`Tap`](crate::Tap), which will observe all data read from
+    /// and written to the pty, through this handle or any other handle to
+    /// the same pty (such as the halves returned by splitting it). Returns
+    /// an id which can be passed to [`remove_tap`](Self::remove_tap).
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.0.get_ref().add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) which was previously attached to the
+    /// pty through any handle. Returns false if the tap was not attached.
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.0.get_ref().remove_tap(id)
+    }
+
+    /// Starts collecting metrics for the pty, and returns a handle for
+    /// querying them. See
+    /// [`blocking::Pty::enable_metrics`](crate::blocking::Pty::enable_metrics).
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.0.get_ref().enable_metrics()
+    }
 }
 
 impl From<Pty> for std::os::fd::OwnedFd {
@@ -83,23 +168,7 @@ impl tokio::io::AsyncRead for Pty {
         cx: &mut std::task::Context<'_>,
         buf: &mut tokio::io::ReadBuf,
     ) -> std::task::Poll<std::io::Result<()>> {
-        loop {
-            let mut guard = match self.0.poll_read_ready(cx) {
-                std::task::Poll::Ready(guard) => guard,
-                std::task::Poll::Pending => return std::task::Poll::Pending,
-            }?;
-            // XXX should be able to optimize this once read_buf is stabilized
-            // in std
-            let b = buf.initialize_unfilled();
-            match guard.try_io(|inner| inner.get_ref().read(b)) {
-                Ok(Ok(bytes)) => {
-                    buf.advance(bytes);
-                    return std::task::Poll::Ready(Ok(()));
-                }
-                Ok(Err(e)) => return std::task::Poll::Ready(Err(e)),
-                Err(_would_block) => continue,
-            }
-        }
+        poll_read(&self.0, cx, buf)
     }
 }
 
@@ -109,39 +178,33 @@ impl tokio::io::AsyncWrite for Pty {
         cx: &mut std::task::Context<'_>,
         buf: &[u8],
     ) -> std::task::Poll<std::io::Result<usize>> {
-        loop {
-            let mut guard = match self.0.poll_write_ready(cx) {
-                std::task::Poll::Ready(guard) => guard,
-                std::task::Poll::Pending => return std::task::Poll::Pending,
-            }?;
-            match guard.try_io(|inner| inner.get_ref().write(buf)) {
-                Ok(result) => return std::task::Poll::Ready(result),
-                Err(_would_block) => continue,
-            }
-        }
+        poll_write(&self.0, cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        poll_write_vectored(&self.0, cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
     }
 
     fn poll_flush(
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<std::io::Result<()>> {
-        loop {
-            let mut guard = match self.0.poll_write_ready(cx) {
-                std::task::Poll::Ready(guard) => guard,
-                std::task::Poll::Pending => return std::task::Poll::Pending,
-            }?;
-            match guard.try_io(|inner| inner.get_ref().flush()) {
-                Ok(_) => return std::task::Poll::Ready(Ok(())),
-                Err(_would_block) => continue,
-            }
-        }
+        poll_flush(&self.0, cx)
     }
 
     fn poll_shutdown(
         self: std::pin::Pin<&mut Self>,
-        _cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<Result<(), std::io::Error>> {
-        std::task::Poll::Ready(Ok(()))
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        poll_shutdown(&self.0, cx)
     }
 }
 
@@ -153,36 +216,59 @@ pub struct Pts(pub(crate) crate::sys::Pts);
 /// Borrowed read half of a [`Pty`]
 pub struct ReadPty<'a>(&'a AsyncPty);
 
-impl<'a> tokio::io::AsyncRead for ReadPty<'a> {
+impl ReadPty<'_> {
+    /// Controls whether reading from the pty after every process holding the
+    /// other end of the pty has closed it reports end of file. See
+    /// [`Pty::set_hangup_as_eof`].
+    pub fn set_hangup_as_eof(&self, enabled: bool) {
+        self.0.get_ref().set_hangup_as_eof(enabled);
+    }
+
+    /// Returns true if a read from the pty has observed that every process
+    /// holding the other end of the pty has closed it. See
+    /// [`Pty::is_hung_up`].
+    #[must_use]
+    pub fn is_hung_up(&self) -> bool {
+        self.0.get_ref().is_hung_up()
+    }
+
+    /// Attaches a [`Tap`](crate::Tap) to the pty. See [`Pty::add_tap`].
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.0.get_ref().add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) from the pty. See
+    /// [`Pty::remove_tap`].
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.0.get_ref().remove_tap(id)
+    }
+
+    /// Starts collecting metrics for the pty, and returns a handle for
+    /// querying them. See [`Pty::enable_metrics`].
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.0.get_ref().enable_metrics()
+    }
+}
+
+impl tokio::io::AsyncRead for ReadPty<'_> {
     fn poll_read(
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
         buf: &mut tokio::io::ReadBuf,
     ) -> std::task::Poll<std::io::Result<()>> {
-        loop {
-            let mut guard = match self.0.poll_read_ready(cx) {
-                std::task::Poll::Ready(guard) => guard,
-                std::task::Poll::Pending => return std::task::Poll::Pending,
-            }?;
-            // XXX should be able to optimize this once read_buf is stabilized
-            // in std
-            let b = buf.initialize_unfilled();
-            match guard.try_io(|inner| inner.get_ref().read(b)) {
-                Ok(Ok(bytes)) => {
-                    buf.advance(bytes);
-                    return std::task::Poll::Ready(Ok(()));
-                }
-                Ok(Err(e)) => return std::task::Poll::Ready(Err(e)),
-                Err(_would_block) => continue,
-            }
-        }
+        poll_read(self.0, cx, buf)
     }
 }
 
 /// Borrowed write half of a [`Pty`]
 pub struct WritePty<'a>(&'a AsyncPty);
 
-impl<'a> WritePty<'a> {
+impl WritePty<'_> {
     /// Change the terminal size associated with the pty.
     ///
     /// # Errors
@@ -190,47 +276,69 @@ impl<'a> WritePty<'a> {
     pub fn resize(&self, size: crate::Size) -> crate::Result<()> {
         self.0.get_ref().set_term_size(size)
     }
+
+    /// Controls whether shutting down the write half sends the `VEOF`
+    /// character to the child. See [`Pty::set_eof_on_shutdown`].
+    pub fn set_eof_on_shutdown(&self, enabled: bool) {
+        self.0.get_ref().set_eof_on_shutdown(enabled);
+    }
+
+    /// Attaches a [`Tap`](crate::Tap) to the pty. See [`Pty::add_tap`].
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.0.get_ref().add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) from the pty. See
+    /// [`Pty::remove_tap`].
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.0.get_ref().remove_tap(id)
+    }
+
+    /// Starts collecting metrics for the pty, and returns a handle for
+    /// querying them. See [`Pty::enable_metrics`].
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.0.get_ref().enable_metrics()
+    }
 }
 
-impl<'a> tokio::io::AsyncWrite for WritePty<'a> {
+impl tokio::io::AsyncWrite for WritePty<'_> {
     fn poll_write(
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
         buf: &[u8],
     ) -> std::task::Poll<std::io::Result<usize>> {
-        loop {
-            let mut guard = match self.0.poll_write_ready(cx) {
-                std::task::Poll::Ready(guard) => guard,
-                std::task::Poll::Pending => return std::task::Poll::Pending,
-            }?;
-            match guard.try_io(|inner| inner.get_ref().write(buf)) {
-                Ok(result) => return std::task::Poll::Ready(result),
-                Err(_would_block) => continue,
-            }
-        }
+        poll_write(self.0, cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        poll_write_vectored(self.0, cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
     }
 
     fn poll_flush(
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<std::io::Result<()>> {
-        loop {
-            let mut guard = match self.0.poll_write_ready(cx) {
-                std::task::Poll::Ready(guard) => guard,
-                std::task::Poll::Pending => return std::task::Poll::Pending,
-            }?;
-            match guard.try_io(|inner| inner.get_ref().flush()) {
-                Ok(_) => return std::task::Poll::Ready(Ok(())),
-                Err(_would_block) => continue,
-            }
-        }
+        poll_flush(self.0, cx)
     }
 
     fn poll_shutdown(
         self: std::pin::Pin<&mut Self>,
-        _cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<Result<(), std::io::Error>> {
-        std::task::Poll::Ready(Ok(()))
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        poll_shutdown(self.0, cx)
     }
 }
 
@@ -262,6 +370,56 @@ impl OwnedReadPty {
             ))
         }
     }
+
+    /// Controls whether reading from the pty after every process holding the
+    /// other end of the pty has closed it reports end of file. See
+    /// [`Pty::set_hangup_as_eof`].
+    pub fn set_hangup_as_eof(&self, enabled: bool) {
+        self.0.get_ref().set_hangup_as_eof(enabled);
+    }
+
+    /// Returns true if a read from the pty has observed that every process
+    /// holding the other end of the pty has closed it. See
+    /// [`Pty::is_hung_up`].
+    #[must_use]
+    pub fn is_hung_up(&self) -> bool {
+        self.0.get_ref().is_hung_up()
+    }
+
+    /// Converts the read half into a `Stream` of the data read from the pty,
+    /// reading at most `chunk_size` bytes at a time. The stream ends once
+    /// reading from the pty reports end of file (see
+    /// [`set_hangup_as_eof`](Self::set_hangup_as_eof)).
+    #[cfg(feature = "codec")]
+    #[must_use]
+    pub fn into_stream(
+        self,
+        chunk_size: usize,
+    ) -> tokio_util::io::ReaderStream<Self> {
+        tokio_util::io::ReaderStream::with_capacity(self, chunk_size)
+    }
+
+    /// Attaches a [`Tap`](crate::Tap) to the pty. See [`Pty::add_tap`].
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.0.get_ref().add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) from the pty. See
+    /// [`Pty::remove_tap`].
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.0.get_ref().remove_tap(id)
+    }
+
+    /// Starts collecting metrics for the pty, and returns a handle for
+    /// querying them. See [`Pty::enable_metrics`].
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.0.get_ref().enable_metrics()
+    }
 }
 
 impl tokio::io::AsyncRead for OwnedReadPty {
@@ -270,23 +428,7 @@ impl tokio::io::AsyncRead for OwnedReadPty {
         cx: &mut std::task::Context<'_>,
         buf: &mut tokio::io::ReadBuf,
     ) -> std::task::Poll<std::io::Result<()>> {
-        loop {
-            let mut guard = match self.0.poll_read_ready(cx) {
-                std::task::Poll::Ready(guard) => guard,
-                std::task::Poll::Pending => return std::task::Poll::Pending,
-            }?;
-            // XXX should be able to optimize this once read_buf is stabilized
-            // in std
-            let b = buf.initialize_unfilled();
-            match guard.try_io(|inner| inner.get_ref().read(b)) {
-                Ok(Ok(bytes)) => {
-                    buf.advance(bytes);
-                    return std::task::Poll::Ready(Ok(()));
-                }
-                Ok(Err(e)) => return std::task::Poll::Ready(Err(e)),
-                Err(_would_block) => continue,
-            }
-        }
+        poll_read(&self.0, cx, buf)
     }
 }
 
@@ -302,6 +444,49 @@ impl OwnedWritePty {
     pub fn resize(&self, size: crate::Size) -> crate::Result<()> {
         self.0.get_ref().set_term_size(size)
     }
+
+    /// Controls whether shutting down the write half sends the `VEOF`
+    /// character to the child. See [`Pty::set_eof_on_shutdown`].
+    pub fn set_eof_on_shutdown(&self, enabled: bool) {
+        self.0.get_ref().set_eof_on_shutdown(enabled);
+    }
+
+    /// Converts the write half into a `Sink` which writes each buffer sent
+    /// to it to the pty. Closing the sink shuts down the write half (see
+    /// [`set_eof_on_shutdown`](Self::set_eof_on_shutdown)).
+    #[cfg(feature = "codec")]
+    #[must_use]
+    pub fn into_sink(
+        self,
+    ) -> tokio_util::codec::FramedWrite<Self, tokio_util::codec::BytesCodec>
+    {
+        tokio_util::codec::FramedWrite::new(
+            self,
+            tokio_util::codec::BytesCodec::new(),
+        )
+    }
+
+    /// Attaches a [`Tap`](crate::Tap) to the pty. See [`Pty::add_tap`].
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.0.get_ref().add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) from the pty. See
+    /// [`Pty::remove_tap`].
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.0.get_ref().remove_tap(id)
+    }
+
+    /// Starts collecting metrics for the pty, and returns a handle for
+    /// querying them. See [`Pty::enable_metrics`].
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.0.get_ref().enable_metrics()
+    }
 }
 
 impl tokio::io::AsyncWrite for OwnedWritePty {
@@ -310,38 +495,157 @@ impl tokio::io::AsyncWrite for OwnedWritePty {
         cx: &mut std::task::Context<'_>,
         buf: &[u8],
     ) -> std::task::Poll<std::io::Result<usize>> {
-        loop {
-            let mut guard = match self.0.poll_write_ready(cx) {
-                std::task::Poll::Ready(guard) => guard,
-                std::task::Poll::Pending => return std::task::Poll::Pending,
-            }?;
-            match guard.try_io(|inner| inner.get_ref().write(buf)) {
-                Ok(result) => return std::task::Poll::Ready(result),
-                Err(_would_block) => continue,
-            }
-        }
+        poll_write(&self.0, cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        poll_write_vectored(&self.0, cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
     }
 
     fn poll_flush(
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<std::io::Result<()>> {
+        poll_flush(&self.0, cx)
+    }
+
+    fn poll_shutdown(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        poll_shutdown(&self.0, cx)
+    }
+}
+
+// the i/o core shared by every async pty handle. all reads and writes go
+// through the sys::Pty methods, which handle end of file mapping, taps, and
+// tracking the last byte written.
+
+fn poll_read(
+    pty: &AsyncPty,
+    cx: &mut std::task::Context<'_>,
+    buf: &mut tokio::io::ReadBuf,
+) -> std::task::Poll<std::io::Result<()>> {
+    loop {
+        let mut guard = match pty.poll_read_ready(cx) {
+            std::task::Poll::Ready(guard) => guard,
+            std::task::Poll::Pending => return std::task::Poll::Pending,
+        }?;
+        // Safety: read_uninit never de-initializes any of the buffer, and
+        // only the bytes it reports as read are marked initialized
+        let b = unsafe { buf.unfilled_mut() };
+        match guard.try_io(|inner| inner.get_ref().read_uninit(b)) {
+            Ok(Ok(bytes)) => {
+                // Safety: read_uninit initialized the first `bytes` bytes of
+                // the unfilled part of the buffer
+                unsafe { buf.assume_init(bytes) };
+                buf.advance(bytes);
+                return std::task::Poll::Ready(Ok(()));
+            }
+            Ok(Err(e)) => return std::task::Poll::Ready(Err(e)),
+            Err(_would_block) => {}
+        }
+    }
+}
+
+fn poll_write(
+    pty: &AsyncPty,
+    cx: &mut std::task::Context<'_>,
+    buf: &[u8],
+) -> std::task::Poll<std::io::Result<usize>> {
+    loop {
+        let mut guard = match pty.poll_write_ready(cx) {
+            std::task::Poll::Ready(guard) => guard,
+            std::task::Poll::Pending => return std::task::Poll::Pending,
+        }?;
+        match guard.try_io(|inner| inner.get_ref().write(buf)) {
+            Ok(result) => return std::task::Poll::Ready(result),
+            Err(_would_block) => {}
+        }
+    }
+}
+
+fn poll_write_vectored(
+    pty: &AsyncPty,
+    cx: &mut std::task::Context<'_>,
+    bufs: &[std::io::IoSlice<'_>],
+) -> std::task::Poll<std::io::Result<usize>> {
+    loop {
+        let mut guard = match pty.poll_write_ready(cx) {
+            std::task::Poll::Ready(guard) => guard,
+            std::task::Poll::Pending => return std::task::Poll::Pending,
+        }?;
+        match guard.try_io(|inner| inner.get_ref().write_vectored(bufs)) {
+            Ok(result) => return std::task::Poll::Ready(result),
+            Err(_would_block) => {}
+        }
+    }
+}
+
+fn poll_flush(
+    pty: &AsyncPty,
+    cx: &mut std::task::Context<'_>,
+) -> std::task::Poll<std::io::Result<()>> {
+    loop {
+        let mut guard = match pty.poll_write_ready(cx) {
+            std::task::Poll::Ready(guard) => guard,
+            std::task::Poll::Pending => return std::task::Poll::Pending,
+        }?;
+        match guard.try_io(|inner| inner.get_ref().flush()) {
+            Ok(_) => return std::task::Poll::Ready(Ok(())),
+            Err(_would_block) => {}
+        }
+    }
+}
+
+// also used by uring::Pty::hangup
+pub fn hangup(
+    pt: &crate::sys::Pty,
+) -> impl std::future::Future<Output = crate::Result<()>> + Send + 'static {
+    // register a separate file descriptor for the same pty, so that waiting
+    // for the hangup doesn't consume (or clear) the readiness that reads on
+    // the pty are waiting for
+    let fd = pt.as_fd().try_clone_to_owned();
+    async move {
+        // see the comment in Pty::new
+        #[allow(deprecated)]
+        let fd = tokio::io::unix::AsyncFd::with_interest(
+            fd?,
+            tokio::io::Interest::READABLE,
+        )?;
         loop {
-            let mut guard = match self.0.poll_write_ready(cx) {
-                std::task::Poll::Ready(guard) => guard,
-                std::task::Poll::Pending => return std::task::Poll::Pending,
-            }?;
-            match guard.try_io(|inner| inner.get_ref().flush()) {
-                Ok(_) => return std::task::Poll::Ready(Ok(())),
-                Err(_would_block) => continue,
+            let mut guard = fd.readable().await?;
+            if guard.ready().is_read_closed() {
+                return Ok(());
             }
+            guard.clear_ready();
         }
     }
+}
 
-    fn poll_shutdown(
-        self: std::pin::Pin<&mut Self>,
-        _cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<Result<(), std::io::Error>> {
-        std::task::Poll::Ready(Ok(()))
+fn poll_shutdown(
+    pty: &AsyncPty,
+    cx: &mut std::task::Context<'_>,
+) -> std::task::Poll<std::io::Result<()>> {
+    if !pty.get_ref().eof_on_shutdown() {
+        return std::task::Poll::Ready(Ok(()));
+    }
+    loop {
+        let mut guard = match pty.poll_write_ready(cx) {
+            std::task::Poll::Ready(guard) => guard,
+            std::task::Poll::Pending => return std::task::Poll::Pending,
+        }?;
+        match guard.try_io(|inner| inner.get_ref().send_eof()) {
+            Ok(result) => return std::task::Poll::Ready(result),
+            Err(_would_block) => {}
+        }
     }
 }
@@ -1,102 +1,471 @@
-use std::os::{
-    fd::{AsRawFd as _, FromRawFd as _},
-    unix::prelude::OsStrExt as _,
-};
+use std::os::{fd::AsRawFd as _, unix::prelude::OsStrExt as _};
+
+// sentinel for "nothing has been written yet", which we treat as being at
+// the start of a line
+#[cfg(any(feature = "async", feature = "async-io"))]
+const NO_LAST_WRITTEN: u16 = u16::MAX;
 
 #[derive(Debug)]
-pub struct Pty(std::os::fd::OwnedFd);
+pub struct Pty {
+    pt: std::os::fd::OwnedFd,
+    name: std::path::PathBuf,
+    hangup_as_eof: std::sync::atomic::AtomicBool,
+    hung_up: std::sync::atomic::AtomicBool,
+    would_block: std::sync::atomic::AtomicBool,
+    taps: crate::tap::Taps,
+    metrics: std::sync::OnceLock<std::sync::Arc<crate::metrics::Collector>>,
+    #[cfg(any(feature = "async", feature = "async-io"))]
+    eof_on_shutdown: std::sync::atomic::AtomicBool,
+    #[cfg(any(feature = "async", feature = "async-io"))]
+    last_written: std::sync::atomic::AtomicU16,
+}
 
 impl Pty {
     pub fn open() -> crate::Result<Self> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("open_pty").entered();
+
         let pt = rustix::pty::openpt(
             // can't use CLOEXEC here because it's linux-specific
             rustix::pty::OpenptFlags::RDWR | rustix::pty::OpenptFlags::NOCTTY,
-        )?;
-        rustix::pty::grantpt(&pt)?;
-        rustix::pty::unlockpt(&pt)?;
+        )
+        .map_err(failed(crate::Operation::Openpt, None))?;
+        rustix::pty::grantpt(&pt)
+            .map_err(failed(crate::Operation::Grantpt, None))?;
+        rustix::pty::unlockpt(&pt)
+            .map_err(failed(crate::Operation::Unlockpt, None))?;
 
         let mut flags = rustix::io::fcntl_getfd(&pt)?;
         flags |= rustix::io::FdFlags::CLOEXEC;
         rustix::io::fcntl_setfd(&pt, flags)?;
 
-        Ok(Self(pt))
+        let name = std::path::PathBuf::from(std::ffi::OsStr::from_bytes(
+            rustix::pty::ptsname(&pt, vec![])
+                .map_err(failed(crate::Operation::Ptsname, None))?
+                .as_bytes(),
+        ));
+        #[cfg(feature = "tracing")]
+        tracing::debug!(pty = %name.display(), "allocated pty");
+
+        Ok(Self {
+            pt,
+            name,
+            hangup_as_eof: std::sync::atomic::AtomicBool::new(true),
+            hung_up: std::sync::atomic::AtomicBool::new(false),
+            would_block: std::sync::atomic::AtomicBool::new(false),
+            taps: crate::tap::Taps::default(),
+            metrics: std::sync::OnceLock::new(),
+            #[cfg(any(feature = "async", feature = "async-io"))]
+            eof_on_shutdown: std::sync::atomic::AtomicBool::new(false),
+            #[cfg(any(feature = "async", feature = "async-io"))]
+            last_written: std::sync::atomic::AtomicU16::new(NO_LAST_WRITTEN),
+        })
     }
 
     pub fn set_term_size(&self, size: crate::Size) -> crate::Result<()> {
         let size = libc::winsize::from(size);
-        let fd = self.0.as_raw_fd();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            pty = %self.name.display(),
+            rows = size.ws_row,
+            cols = size.ws_col,
+            "resizing pty"
+        );
+        let fd = self.pt.as_raw_fd();
         // TODO: upstream this to rustix
         let ret = unsafe {
             libc::ioctl(fd, libc::TIOCSWINSZ, std::ptr::addr_of!(size))
         };
         if ret == -1 {
-            Err(rustix::io::Errno::from_raw_os_error(
-                std::io::Error::last_os_error().raw_os_error().unwrap_or(0),
-            )
-            .into())
+            Err(failed(crate::Operation::Resize, Some(&self.name))(
+                std::io::Error::last_os_error(),
+            ))
         } else {
             Ok(())
         }
     }
 
     pub fn pts(&self) -> crate::Result<Pts> {
-        Ok(Pts(std::fs::OpenOptions::new()
+        let fd = std::fs::OpenOptions::new()
             .read(true)
             .write(true)
-            .open(std::ffi::OsStr::from_bytes(
-                rustix::pty::ptsname(&self.0, vec![])?.as_bytes(),
-            ))?
-            .into()))
+            .open(&self.name)
+            .map_err(failed(crate::Operation::OpenPts, Some(&self.name)))?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(pty = %self.name.display(), "opened pts");
+        Ok(Pts {
+            fd: fd.into(),
+            name: self.name.clone(),
+        })
     }
 
-    #[cfg(feature = "async")]
-    pub fn set_nonblocking(&self) -> rustix::io::Result<()> {
-        let mut opts = rustix::fs::fcntl_getfl(&self.0)?;
-        opts |= rustix::fs::OFlags::NONBLOCK;
-        rustix::fs::fcntl_setfl(&self.0, opts)?;
+    pub fn set_nonblocking(
+        &self,
+        nonblocking: bool,
+    ) -> rustix::io::Result<()> {
+        let mut opts = rustix::fs::fcntl_getfl(&self.pt)?;
+        opts.set(rustix::fs::OFlags::NONBLOCK, nonblocking);
+        rustix::fs::fcntl_setfl(&self.pt, opts)?;
 
         Ok(())
     }
+
+    // waits for any of the given events on the pty, returning the events
+    // that occurred, or an empty set if the deadline passed first. POLLHUP
+    // is always reported, even if it isn't requested.
+    pub fn poll(
+        &self,
+        events: rustix::event::PollFlags,
+        deadline: Option<std::time::Instant>,
+    ) -> std::io::Result<rustix::event::PollFlags> {
+        loop {
+            let timeout = deadline.map_or(-1, |deadline| {
+                let remaining = deadline
+                    .saturating_duration_since(std::time::Instant::now());
+                // round up, so that we don't spin when less than a
+                // millisecond remains
+                let millis = remaining.as_nanos().div_ceil(1_000_000);
+                i32::try_from(millis).unwrap_or(i32::MAX)
+            });
+            let mut fds = [rustix::event::PollFd::new(&self.pt, events)];
+            match rustix::event::poll(&mut fds, timeout) {
+                Ok(_) => return Ok(fds[0].revents()),
+                Err(rustix::io::Errno::INTR) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    // whether reads and writes from the blocking api should report
+    // WouldBlock to the caller rather than waiting for readiness. the fd
+    // used by the blocking api is always in non-blocking mode, so this only
+    // changes how that is handled.
+    pub fn set_would_block(&self, enabled: bool) {
+        self.would_block
+            .store(enabled, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn would_block(&self) -> bool {
+        self.would_block.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub fn set_hangup_as_eof(&self, enabled: bool) {
+        self.hangup_as_eof
+            .store(enabled, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn is_hung_up(&self) -> bool {
+        self.hung_up.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    // on linux, reading from the pty after every file descriptor for the
+    // pts has been closed returns EIO rather than end of file (other
+    // platforms return end of file directly)
+    fn read_checked(&self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let res =
+            rustix::io::read(&self.pt, buf).map_err(std::io::Error::from);
+        if let Ok(bytes) = res {
+            self.taps.read(&buf[..bytes]);
+        }
+        self.check_read(res, buf.len())
+    }
+
+    // applies the hangup handling from read_checked to the result of a read
+    // of up to len bytes which was performed some other way
+    pub fn check_read(
+        &self,
+        res: std::io::Result<usize>,
+        len: usize,
+    ) -> std::io::Result<usize> {
+        #[cfg(feature = "tracing")]
+        match &res {
+            Ok(bytes) => {
+                tracing::trace!(pty = %self.name.display(), bytes, "read");
+            }
+            Err(e) => {
+                tracing::trace!(
+                    pty = %self.name.display(),
+                    error = %e,
+                    "read failed"
+                );
+            }
+        }
+        if let (Ok(bytes), Some(metrics)) = (&res, self.metrics.get()) {
+            metrics.read(*bytes);
+        }
+        match res {
+            Ok(0) if len > 0 => {
+                self.hung_up
+                    .store(true, std::sync::atomic::Ordering::Relaxed);
+                Ok(0)
+            }
+            Err(e)
+                if e.raw_os_error()
+                    == Some(rustix::io::Errno::IO.raw_os_error()) =>
+            {
+                self.hung_up
+                    .store(true, std::sync::atomic::Ordering::Relaxed);
+                if self
+                    .hangup_as_eof
+                    .load(std::sync::atomic::Ordering::Relaxed)
+                {
+                    Ok(0)
+                } else {
+                    Err(e)
+                }
+            }
+            res => res,
+        }
+    }
+
+    #[cfg(any(feature = "async", feature = "async-io"))]
+    pub fn eof_on_shutdown(&self) -> bool {
+        self.eof_on_shutdown
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    #[cfg(any(feature = "async", feature = "async-io"))]
+    pub fn set_eof_on_shutdown(&self, enabled: bool) {
+        self.eof_on_shutdown
+            .store(enabled, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    // writes the VEOF character so that a process reading from the pts
+    // sees end of file. this only means anything in canonical mode, and
+    // VEOF only signals end of file when it is the first character on a
+    // line (otherwise it just submits the partial line), so we send it a
+    // second time if the last thing we wrote didn't end a line.
+    #[cfg(any(feature = "async", feature = "async-io"))]
+    pub fn send_eof(&self) -> std::io::Result<()> {
+        let termios = rustix::termios::tcgetattr(&self.pt)?;
+        if !termios
+            .local_modes
+            .contains(rustix::termios::LocalModes::ICANON)
+        {
+            return Ok(());
+        }
+        let veof =
+            termios.special_codes[rustix::termios::SpecialCodeIndex::VEOF];
+        // _POSIX_VDISABLE
+        if veof == 0 {
+            return Ok(());
+        }
+
+        loop {
+            let at_line_start = self.at_line_start(&termios);
+            if self.write_tracked(&[veof])? == 0 {
+                return Err(std::io::ErrorKind::WriteZero.into());
+            }
+            if at_line_start {
+                return Ok(());
+            }
+        }
+    }
+
+    #[cfg(any(feature = "async", feature = "async-io"))]
+    fn at_line_start(&self, termios: &rustix::termios::Termios) -> bool {
+        let Ok(last) = u8::try_from(
+            self.last_written.load(std::sync::atomic::Ordering::Relaxed),
+        ) else {
+            return true;
+        };
+        let special = |idx| {
+            let c = termios.special_codes[idx];
+            c != 0 && c == last
+        };
+        last == b'\n'
+            || (last == b'\r'
+                && termios
+                    .input_modes
+                    .contains(rustix::termios::InputModes::ICRNL)
+                && !termios
+                    .input_modes
+                    .contains(rustix::termios::InputModes::IGNCR))
+            || special(rustix::termios::SpecialCodeIndex::VEOF)
+            || special(rustix::termios::SpecialCodeIndex::VEOL)
+            || special(rustix::termios::SpecialCodeIndex::VEOL2)
+    }
+
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.taps.add(tap)
+    }
+
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.taps.remove(id)
+    }
+
+    // reports data which was read from the pty some other way to the taps
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    pub fn tap_read(&self, data: &[u8]) {
+        self.taps.read(data);
+    }
+
+    // reads into possibly uninitialized memory, returning the number of
+    // bytes at the start of buf which have been initialized by the read
+    #[cfg(feature = "async")]
+    pub fn read_uninit(
+        &self,
+        buf: &mut [std::mem::MaybeUninit<u8>],
+    ) -> std::io::Result<usize> {
+        let len = buf.len();
+        let res = match rustix::io::read_uninit(&self.pt, buf) {
+            Ok((init, _)) => {
+                self.taps.read(init);
+                Ok(init.len())
+            }
+            Err(e) => Err(e.into()),
+        };
+        self.check_read(res, len)
+    }
+
+    fn read_vectored_checked(
+        &self,
+        bufs: &mut [std::io::IoSliceMut<'_>],
+    ) -> std::io::Result<usize> {
+        let len = bufs.iter().map(|buf| buf.len()).sum();
+        let res =
+            rustix::io::readv(&self.pt, bufs).map_err(std::io::Error::from);
+        if let Ok(bytes) = res {
+            let mut remaining = bytes;
+            for buf in bufs.iter() {
+                let read = remaining.min(buf.len());
+                self.taps.read(&buf[..read]);
+                remaining -= read;
+            }
+        }
+        self.check_read(res, len)
+    }
+
+    fn write_tracked(&self, buf: &[u8]) -> std::io::Result<usize> {
+        let bytes = self.record_write(rustix::io::write(&self.pt, buf))?;
+        self.wrote(&buf[..bytes]);
+        Ok(bytes)
+    }
+
+    fn write_vectored_tracked(
+        &self,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> std::io::Result<usize> {
+        let bytes = self.record_write(rustix::io::writev(&self.pt, bufs))?;
+        let mut remaining = bytes;
+        for buf in bufs {
+            let written = remaining.min(buf.len());
+            self.wrote(&buf[..written]);
+            remaining -= written;
+        }
+        Ok(bytes)
+    }
+
+    // updates the metrics (if enabled) for a single write to the pty
+    pub fn record_write(
+        &self,
+        res: rustix::io::Result<usize>,
+    ) -> rustix::io::Result<usize> {
+        #[cfg(feature = "tracing")]
+        match res {
+            Ok(bytes) => {
+                tracing::trace!(pty = %self.name.display(), bytes, "wrote");
+            }
+            Err(e) => {
+                tracing::trace!(
+                    pty = %self.name.display(),
+                    error = %e,
+                    "write failed"
+                );
+            }
+        }
+        if let Some(metrics) = self.metrics.get() {
+            match res {
+                Ok(bytes) => metrics.write(bytes),
+                Err(rustix::io::Errno::AGAIN) => metrics.stall(),
+                Err(_) => {}
+            }
+        }
+        res
+    }
+
+    // for writes which wait for space in the pty's buffer without
+    // reporting EAGAIN to us (io_uring waits internally), starts a write
+    // stall if the buffer is currently full
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    pub fn check_stall(&self) {
+        if let Some(metrics) = self.metrics.get() {
+            if self
+                .poll(
+                    rustix::event::PollFlags::OUT,
+                    Some(std::time::Instant::now()),
+                )
+                .is_ok_and(|events| events.is_empty())
+            {
+                metrics.stall();
+            }
+        }
+    }
+
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        crate::MetricsHandle(std::sync::Arc::clone(
+            self.metrics.get_or_init(std::sync::Arc::default),
+        ))
+    }
+
+    // records data which was written to the pty, either by one of the
+    // methods above or some other way
+    pub fn wrote(&self, data: &[u8]) {
+        self.taps.write(data);
+        #[cfg(any(feature = "async", feature = "async-io"))]
+        if let Some(last) = data.last() {
+            self.last_written.store(
+                u16::from(*last),
+                std::sync::atomic::Ordering::Relaxed,
+            );
+        }
+    }
 }
 
 impl From<Pty> for std::os::fd::OwnedFd {
     fn from(pty: Pty) -> Self {
-        let Pty(nix_ptymaster) = pty;
-        let raw_fd = nix_ptymaster.as_raw_fd();
-        std::mem::forget(nix_ptymaster);
-
-        // Safety: nix::pty::PtyMaster is required to contain a valid file
-        // descriptor, and we ensured that the file descriptor will remain
-        // valid by skipping the drop implementation for nix::pty::PtyMaster
-        unsafe { Self::from_raw_fd(raw_fd) }
+        pty.pt
     }
 }
 
 impl std::os::fd::AsFd for Pty {
     fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
-        let raw_fd = self.0.as_raw_fd();
-
-        // Safety: nix::pty::PtyMaster is required to contain a valid file
-        // descriptor, and it is owned by self
-        unsafe { std::os::fd::BorrowedFd::borrow_raw(raw_fd) }
+        self.pt.as_fd()
     }
 }
 
 impl std::os::fd::AsRawFd for Pty {
     fn as_raw_fd(&self) -> std::os::fd::RawFd {
-        self.0.as_raw_fd()
+        self.pt.as_raw_fd()
     }
 }
 
 impl std::io::Read for Pty {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        rustix::io::read(&self.0, buf).map_err(std::io::Error::from)
+        self.read_checked(buf)
+    }
+
+    fn read_vectored(
+        &mut self,
+        bufs: &mut [std::io::IoSliceMut<'_>],
+    ) -> std::io::Result<usize> {
+        self.read_vectored_checked(bufs)
     }
 }
 
 impl std::io::Write for Pty {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        rustix::io::write(&self.0, buf).map_err(std::io::Error::from)
+        self.write_tracked(buf)
+    }
+
+    fn write_vectored(
+        &mut self,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> std::io::Result<usize> {
+        self.write_vectored_tracked(bufs)
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
@@ -106,13 +475,27 @@ impl std::io::Write for Pty {
 
 impl std::io::Read for &Pty {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        rustix::io::read(&self.0, buf).map_err(std::io::Error::from)
+        self.read_checked(buf)
+    }
+
+    fn read_vectored(
+        &mut self,
+        bufs: &mut [std::io::IoSliceMut<'_>],
+    ) -> std::io::Result<usize> {
+        self.read_vectored_checked(bufs)
     }
 }
 
 impl std::io::Write for &Pty {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        rustix::io::write(&self.0, buf).map_err(std::io::Error::from)
+        self.write_tracked(buf)
+    }
+
+    fn write_vectored(
+        &mut self,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> std::io::Result<usize> {
+        self.write_vectored_tracked(bufs)
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
@@ -120,7 +503,10 @@ impl std::io::Write for &Pty {
     }
 }
 
-pub struct Pts(std::os::fd::OwnedFd);
+pub struct Pts {
+    fd: std::os::fd::OwnedFd,
+    name: std::path::PathBuf,
+}
 
 impl Pts {
     pub fn setup_subprocess(
@@ -130,39 +516,50 @@ impl Pts {
         std::process::Stdio,
         std::process::Stdio,
     )> {
-        Ok((
-            self.0.try_clone()?.into(),
-            self.0.try_clone()?.into(),
-            self.0.try_clone()?.into(),
-        ))
+        #[cfg(feature = "tracing")]
+        tracing::debug!(pty = %self.name.display(), "spawning child");
+        let dup = || {
+            let fd = self.fd.try_clone();
+            #[cfg(feature = "tracing")]
+            if let Err(e) = &fd {
+                tracing::debug!(
+                    pty = %self.name.display(),
+                    error = %e,
+                    "failed to duplicate pts for stdio"
+                );
+            }
+            fd
+        };
+        Ok((dup()?.into(), dup()?.into(), dup()?.into()))
     }
 
-    pub fn session_leader(&self) -> impl FnMut() -> std::io::Result<()> {
-        let pts_fd = self.0.as_raw_fd();
-        move || {
-            rustix::process::setsid()?;
-            rustix::process::ioctl_tiocsctty(unsafe {
-                std::os::fd::BorrowedFd::borrow_raw(pts_fd)
-            })?;
-            Ok(())
-        }
+    pub fn name(&self) -> &std::path::Path {
+        &self.name
     }
 }
 
 impl From<Pts> for std::os::fd::OwnedFd {
     fn from(pts: Pts) -> Self {
-        pts.0
+        pts.fd
     }
 }
 
 impl std::os::fd::AsFd for Pts {
     fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
-        self.0.as_fd()
+        self.fd.as_fd()
     }
 }
 
 impl std::os::fd::AsRawFd for Pts {
     fn as_raw_fd(&self) -> std::os::fd::RawFd {
-        self.0.as_raw_fd()
+        self.fd.as_raw_fd()
     }
 }
+
+// reports a failure of the given operation
+fn failed<E: Into<std::io::Error>>(
+    operation: crate::Operation,
+    pty: Option<&std::path::Path>,
+) -> impl FnOnce(E) -> crate::Error + '_ {
+    move |e| crate::Error::operation_failed(operation, pty, None, e)
+}
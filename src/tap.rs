@@ -0,0 +1,132 @@
+/// Observes the data passing through a pty
+///
+/// Taps are attached to a pty with `add_tap` (for instance,
+/// [`blocking::Pty::add_tap`](crate::blocking::Pty::add_tap)), and are then
+/// called for every chunk of data read from or written to the pty through
+/// any handle to it, including the halves returned by splitting it. Any
+/// number of taps can be attached to the same pty, and they are called in
+/// the order they were added. Taps are called synchronously from within the
+/// read or write, so they should not block.
+pub trait Tap: Send + Sync {
+    /// Called with the data returned by each successful read from the pty.
+    fn on_read(&self, chunk: Chunk<'_>) {
+        let _ = chunk;
+    }
+
+    /// Called with the data accepted by each successful write to the pty.
+    /// This may be a prefix of the data that was passed to the write.
+    fn on_write(&self, chunk: Chunk<'_>) {
+        let _ = chunk;
+    }
+}
+
+/// A chunk of data read from or written to a pty, as seen by a [`Tap`]
+#[derive(Debug, Clone, Copy)]
+pub struct Chunk<'a> {
+    data: &'a [u8],
+    time: std::time::Instant,
+}
+
+impl<'a> Chunk<'a> {
+    /// Returns the data that was read or written.
+    #[must_use]
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns the time at which the read or write completed.
+    #[must_use]
+    pub fn time(&self) -> std::time::Instant {
+        self.time
+    }
+}
+
+/// Identifies a [`Tap`] attached to a pty, so that it can be removed again
+/// via `remove_tap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TapId(u64);
+
+type TapList = std::sync::Arc<[(TapId, std::sync::Arc<dyn Tap>)]>;
+
+// the list is replaced (rather than modified) when adding or removing taps,
+// so that reads and writes only need to clone the current list, and can
+// call the taps without holding the lock (so that taps can add or remove
+// taps themselves)
+#[derive(Default)]
+pub struct Taps {
+    taps: std::sync::RwLock<TapList>,
+    next_id: std::sync::atomic::AtomicU64,
+}
+
+impl Taps {
+    pub fn add(&self, tap: std::sync::Arc<dyn Tap>) -> TapId {
+        let id = TapId(
+            self.next_id
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+        );
+        let mut taps = self
+            .taps
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        *taps = taps.iter().cloned().chain([(id, tap)]).collect();
+        id
+    }
+
+    pub fn remove(&self, id: TapId) -> bool {
+        let mut taps = self
+            .taps
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let len = taps.len();
+        *taps = taps
+            .iter()
+            .filter(|(tap_id, _)| *tap_id != id)
+            .cloned()
+            .collect();
+        taps.len() != len
+    }
+
+    pub fn read(&self, data: &[u8]) {
+        self.each(data, |tap, chunk| tap.on_read(chunk));
+    }
+
+    pub fn write(&self, data: &[u8]) {
+        self.each(data, |tap, chunk| tap.on_write(chunk));
+    }
+
+    fn each(&self, data: &[u8], f: impl Fn(&dyn Tap, Chunk<'_>)) {
+        if data.is_empty() {
+            return;
+        }
+        let taps = std::sync::Arc::clone(
+            &self
+                .taps
+                .read()
+                .unwrap_or_else(std::sync::PoisonError::into_inner),
+        );
+        if taps.is_empty() {
+            return;
+        }
+        let chunk = Chunk {
+            data,
+            time: std::time::Instant::now(),
+        };
+        for (_, tap) in taps.iter() {
+            f(tap.as_ref(), chunk);
+        }
+    }
+}
+
+impl std::fmt::Debug for Taps {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let len = self
+            .taps
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .len();
+        f.debug_struct("Taps")
+            .field("len", &len)
+            .field("next_id", &self.next_id)
+            .finish()
+    }
+}
@@ -48,3 +48,147 @@ impl From<Size> for libc::winsize {
         }
     }
 }
+
+/// Controls which session and process group the child process is placed in,
+/// and whether the pty becomes its controlling terminal. See
+/// [`Command::session_mode`](crate::blocking::Command::session_mode).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SessionMode {
+    /// The child starts a new session (and so also a new process group),
+    /// and the pty becomes its controlling terminal. This is the default.
+    #[default]
+    NewSession,
+    /// The child starts a new process group within the session of the
+    /// calling process, and keeps the caller's controlling terminal (if
+    /// any). The pty is only used for the child's stdio. This is useful for
+    /// running jobs under a supervisor which manages the session itself.
+    NewProcessGroup,
+    /// The child starts a new session, but without a controlling terminal.
+    /// The pty is only used for the child's stdio, so the child won't
+    /// receive signals generated by the pty (such as `SIGINT` from `^C`).
+    /// Note that on some systems (including Linux), a session leader
+    /// without a controlling terminal acquires any terminal that it opens
+    /// without `O_NOCTTY`, so if the child opens the pty itself, the pty
+    /// may still become its controlling terminal.
+    NoControllingTerminal,
+}
+
+/// A resource whose usage can be limited for the child process. See
+/// [`Command::rlimit`](crate::blocking::Command::rlimit).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Resource {
+    /// CPU time, in seconds (`RLIMIT_CPU`)
+    Cpu,
+    /// The size of files which can be created, in bytes (`RLIMIT_FSIZE`)
+    FileSize,
+    /// The size of the data segment, in bytes (`RLIMIT_DATA`)
+    Data,
+    /// The size of the stack, in bytes (`RLIMIT_STACK`)
+    Stack,
+    /// The size of core dumps, in bytes (`RLIMIT_CORE`)
+    Core,
+    /// The number of processes for the child's user (`RLIMIT_NPROC`)
+    Processes,
+    /// The number of open file descriptors (`RLIMIT_NOFILE`)
+    OpenFiles,
+    /// The amount of memory which can be locked, in bytes
+    /// (`RLIMIT_MEMLOCK`)
+    LockedMemory,
+    /// The size of the virtual address space, in bytes (`RLIMIT_AS`)
+    AddressSpace,
+}
+
+impl From<Resource> for rustix::process::Resource {
+    fn from(resource: Resource) -> Self {
+        match resource {
+            Resource::Cpu => Self::Cpu,
+            Resource::FileSize => Self::Fsize,
+            Resource::Data => Self::Data,
+            Resource::Stack => Self::Stack,
+            Resource::Core => Self::Core,
+            Resource::Processes => Self::Nproc,
+            Resource::OpenFiles => Self::Nofile,
+            Resource::LockedMemory => Self::Memlock,
+            Resource::AddressSpace => Self::As,
+        }
+    }
+}
+
+/// The I/O scheduling class and priority of the child process (Linux only).
+/// See [`Command::io_priority`](crate::blocking::Command::io_priority) and
+/// `ioprio_set(2)`.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IoPriority {
+    /// The real time class, with the given priority level (0 is the
+    /// highest, and 7 is the lowest)
+    RealTime(u8),
+    /// The best effort class, with the given priority level (0 is the
+    /// highest, and 7 is the lowest)
+    BestEffort(u8),
+    /// The idle class, which only gets I/O time when no other process needs
+    /// it
+    Idle,
+}
+
+#[cfg(target_os = "linux")]
+impl IoPriority {
+    // the value passed to ioprio_set (see IOPRIO_PRIO_VALUE in
+    // linux/ioprio.h)
+    pub(crate) fn to_raw(self) -> libc::c_int {
+        let class = match self {
+            Self::RealTime(_) => 1,
+            Self::BestEffort(_) => 2,
+            Self::Idle => 3,
+        };
+        (class << 13) | libc::c_int::from(self.level())
+    }
+
+    pub(crate) fn level(self) -> u8 {
+        match self {
+            Self::RealTime(level) | Self::BestEffort(level) => level,
+            Self::Idle => 0,
+        }
+    }
+}
+
+/// A user to run the child process as, given either by name or by uid. See
+/// [`Command::as_user`](crate::blocking::Command::as_user).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum User {
+    /// The user with the given login name
+    Name(std::ffi::OsString),
+    /// The user with the given uid
+    Id(u32),
+}
+
+impl From<&str> for User {
+    fn from(name: &str) -> Self {
+        Self::Name(name.into())
+    }
+}
+
+impl From<String> for User {
+    fn from(name: String) -> Self {
+        Self::Name(name.into())
+    }
+}
+
+impl From<&std::ffi::OsStr> for User {
+    fn from(name: &std::ffi::OsStr) -> Self {
+        Self::Name(name.into())
+    }
+}
+
+impl From<std::ffi::OsString> for User {
+    fn from(name: std::ffi::OsString) -> Self {
+        Self::Name(name)
+    }
+}
+
+impl From<u32> for User {
+    fn from(uid: u32) -> Self {
+        Self::Id(uid)
+    }
+}
@@ -0,0 +1,18 @@
+//! `io_uring` based equivalent of [`pty_process::Pty`](crate::Pty)
+//!
+//! This uses [`tokio_uring`] to submit reads and writes to the kernel
+//! directly, rather than waiting for readiness and then making a separate
+//! syscall for each chunk of data. Reads and writes can also use buffers
+//! registered with the kernel ahead of time (see
+//! [`tokio_uring::buf::fixed`]). It must be used from within a
+//! [`tokio_uring`] runtime, and is only available on Linux.
+//!
+//! Since the kernel owns the buffers for the duration of each operation,
+//! the pty types here don't implement [`tokio::io::AsyncRead`] and
+//! [`tokio::io::AsyncWrite`]. Instead, reads and writes take ownership of
+//! the buffer and return it along with the result, as with
+//! [`tokio_uring::fs::File`]. Processes are spawned on the pty using
+//! [`pty_process::Command`](crate::Command) as usual.
+
+mod pty;
+pub use pty::{OwnedReadPty, OwnedWritePty, Pty, ReadPty, WritePty};
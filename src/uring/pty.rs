@@ -0,0 +1,760 @@
+#![allow(clippy::module_name_repetitions)]
+// tokio_uring is single threaded, so none of its futures are Send
+#![allow(clippy::future_not_send)]
+
+use std::os::fd::AsFd as _;
+use tokio_uring::buf::BoundedBuf as _;
+
+struct UringPty {
+    pt: crate::sys::Pty,
+    file: tokio_uring::fs::File,
+    // only used to wait for readiness if the kernel ever reports EAGAIN to
+    // us (see UringPty::open)
+    // registered lazily, since a pty can be created outside of the runtime
+    ready:
+        std::sync::OnceLock<tokio::io::unix::AsyncFd<std::os::fd::OwnedFd>>,
+}
+
+impl UringPty {
+    fn open() -> crate::Result<Self> {
+        let pt = crate::sys::Pty::open()?;
+        // io_uring can only try a read or write on a tty without blocking
+        // (and otherwise falls back to waiting for readiness internally)
+        // if the fd is in non-blocking mode. in blocking mode, every read
+        // and write is punted to a worker thread instead.
+        pt.set_nonblocking(true)?;
+        // the file closes its own copy of the fd, and we still need the
+        // original for ioctls
+        let fd = pt.as_fd().try_clone_to_owned()?;
+        let file = tokio_uring::fs::File::from_std(std::fs::File::from(fd));
+        Ok(Self {
+            pt,
+            file,
+            ready: std::sync::OnceLock::new(),
+        })
+    }
+
+    // ptys aren't seekable, so the offsets passed to the file methods below
+    // are ignored
+    async fn read<T: tokio_uring::buf::BoundedBufMut>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<usize, T> {
+        let len = buf.bytes_total();
+        let (res, buf) = self
+            .retry(buf, tokio::io::Interest::READABLE, |buf| {
+                self.file.read_at(buf, 0)
+            })
+            .await;
+        self.tap_read(&res, &buf);
+        (self.pt.check_read(res, len), buf)
+    }
+
+    async fn read_fixed<T>(&self, buf: T) -> tokio_uring::BufResult<usize, T>
+    where
+        T: tokio_uring::buf::BoundedBufMut<
+            BufMut = tokio_uring::buf::fixed::FixedBuf,
+        >,
+    {
+        let len = buf.bytes_total();
+        let (res, buf) = self
+            .retry(buf, tokio::io::Interest::READABLE, |buf| {
+                self.file.read_fixed_at(buf, 0)
+            })
+            .await;
+        self.tap_read(&res, &buf);
+        (self.pt.check_read(res, len), buf)
+    }
+
+    async fn write<T: tokio_uring::buf::BoundedBuf>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<usize, T> {
+        self.pt.check_stall();
+        let (res, buf) = self
+            .retry(buf, tokio::io::Interest::WRITABLE, |buf| {
+                self.file.write_at(buf, 0).submit()
+            })
+            .await;
+        self.tap_write(&res, &buf);
+        (res, buf)
+    }
+
+    async fn write_all<T: tokio_uring::buf::BoundedBuf>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<(), T> {
+        let bounds = buf.bounds();
+        let mut buf = buf.slice_full();
+        while buf.bytes_init() > 0 {
+            match self.write(buf).await {
+                (Ok(0), slice) => {
+                    return (
+                        Err(std::io::ErrorKind::WriteZero.into()),
+                        T::from_buf_bounds(slice.into_inner(), bounds),
+                    );
+                }
+                (Ok(bytes), slice) => buf = slice.slice(bytes..),
+                (Err(e), slice) => {
+                    return (
+                        Err(e),
+                        T::from_buf_bounds(slice.into_inner(), bounds),
+                    );
+                }
+            }
+        }
+        (Ok(()), T::from_buf_bounds(buf.into_inner(), bounds))
+    }
+
+    async fn write_fixed<T>(&self, buf: T) -> tokio_uring::BufResult<usize, T>
+    where
+        T: tokio_uring::buf::BoundedBuf<
+            Buf = tokio_uring::buf::fixed::FixedBuf,
+        >,
+    {
+        self.pt.check_stall();
+        let (res, buf) = self
+            .retry(buf, tokio::io::Interest::WRITABLE, |buf| {
+                self.file.write_fixed_at(buf, 0)
+            })
+            .await;
+        self.tap_write(&res, &buf);
+        (res, buf)
+    }
+
+    async fn write_fixed_all<T>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<(), T>
+    where
+        T: tokio_uring::buf::BoundedBuf<
+            Buf = tokio_uring::buf::fixed::FixedBuf,
+        >,
+    {
+        let bounds = buf.bounds();
+        let mut buf = buf.slice_full();
+        while buf.bytes_init() > 0 {
+            match self.write_fixed(buf).await {
+                (Ok(0), slice) => {
+                    return (
+                        Err(std::io::ErrorKind::WriteZero.into()),
+                        T::from_buf_bounds(slice.into_inner(), bounds),
+                    );
+                }
+                (Ok(bytes), slice) => buf = slice.slice(bytes..),
+                (Err(e), slice) => {
+                    return (
+                        Err(e),
+                        T::from_buf_bounds(slice.into_inner(), bounds),
+                    );
+                }
+            }
+        }
+        (Ok(()), T::from_buf_bounds(buf.into_inner(), bounds))
+    }
+
+    // current kernels wait for readiness internally rather than reporting
+    // EAGAIN for a non-blocking fd which supports polling, but older ones
+    // return it to us, so wait for readiness ourselves in that case
+    async fn retry<T, F>(
+        &self,
+        mut buf: T,
+        interest: tokio::io::Interest,
+        mut op: impl FnMut(T) -> F,
+    ) -> tokio_uring::BufResult<usize, T>
+    where
+        F: std::future::Future<Output = tokio_uring::BufResult<usize, T>>,
+    {
+        loop {
+            let (res, ret) = op(buf).await;
+            buf = ret;
+            match res {
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    if interest.is_writable() {
+                        let _ = self
+                            .pt
+                            .record_write(Err(rustix::io::Errno::AGAIN));
+                    }
+                    if let Err(e) = self.wait_ready(interest).await {
+                        return (Err(e), buf);
+                    }
+                }
+                res => return (res, buf),
+            }
+        }
+    }
+
+    async fn wait_ready(
+        &self,
+        interest: tokio::io::Interest,
+    ) -> std::io::Result<()> {
+        let events = if interest.is_readable() {
+            rustix::event::PollFlags::IN
+        } else {
+            rustix::event::PollFlags::OUT
+        };
+        let ready = if let Some(ready) = self.ready.get() {
+            ready
+        } else {
+            // AsyncFd::new is deprecated because it can't guarantee that the
+            // fd outlives the registration, but the AsyncFd owns the
+            // duplicated fd
+            #[allow(deprecated)]
+            let ready = tokio::io::unix::AsyncFd::new(
+                self.pt.as_fd().try_clone_to_owned()?,
+            )?;
+            self.ready.get_or_init(|| ready)
+        };
+        loop {
+            let mut guard = if interest.is_readable() {
+                ready.readable().await?
+            } else {
+                ready.writable().await?
+            };
+            // the cached readiness may be stale, since the operations
+            // themselves don't go through the AsyncFd
+            if !self
+                .pt
+                .poll(events, Some(std::time::Instant::now()))?
+                .is_empty()
+            {
+                return Ok(());
+            }
+            guard.clear_ready();
+        }
+    }
+
+    // the file methods read into and write from the start of the buffer
+    fn tap_read<T: tokio_uring::buf::BoundedBuf, E>(
+        &self,
+        res: &Result<usize, E>,
+        buf: &T,
+    ) {
+        if let Ok(bytes) = res {
+            // Safety: the kernel initialized this many bytes at the start of
+            // the buffer
+            self.pt.tap_read(unsafe {
+                std::slice::from_raw_parts(buf.stable_ptr(), *bytes)
+            });
+        }
+    }
+
+    fn tap_write<T: tokio_uring::buf::BoundedBuf>(
+        &self,
+        res: &std::io::Result<usize>,
+        buf: &T,
+    ) {
+        let _ = self.pt.record_write(match res {
+            Ok(bytes) => Ok(*bytes),
+            Err(e) => Err(rustix::io::Errno::from_io_error(e)
+                .unwrap_or(rustix::io::Errno::IO)),
+        });
+        if let Ok(bytes) = res {
+            // Safety: the bytes written came from the initialized part of
+            // the buffer
+            self.pt.wrote(unsafe {
+                std::slice::from_raw_parts(buf.stable_ptr(), *bytes)
+            });
+        }
+    }
+}
+
+/// An allocated pty, using `io_uring` for reads and writes
+pub struct Pty(UringPty);
+
+impl Pty {
+    /// Allocate and return a new pty.
+    ///
+    /// # Errors
+    /// Returns an error if the pty failed to be allocated, or if we were
+    /// unable to duplicate its file descriptor.
+    pub fn new() -> crate::Result<Self> {
+        Ok(Self(UringPty::open()?))
+    }
+
+    /// Change the terminal size associated with the pty.
+    ///
+    /// # Errors
+    /// Returns an error if we were unable to set the terminal size.
+    pub fn resize(&self, size: crate::Size) -> crate::Result<()> {
+        self.0.pt.set_term_size(size)
+    }
+
+    /// Opens a file descriptor for the other end of the pty, which should be
+    /// attached to the child process running in it. See
+    /// [`Command::spawn`](crate::Command::spawn).
+    ///
+    /// # Errors
+    /// Returns an error if the device node to open could not be determined,
+    /// or if the device node could not be opened.
+    pub fn pts(&self) -> crate::Result<crate::Pts> {
+        Ok(crate::Pts(self.0.pt.pts()?))
+    }
+
+    /// Controls whether reading from the pty after every process holding the
+    /// other end of the pty has closed it reports end of file. See
+    /// [`crate::Pty::set_hangup_as_eof`].
+    pub fn set_hangup_as_eof(&self, enabled: bool) {
+        self.0.pt.set_hangup_as_eof(enabled);
+    }
+
+    /// Returns true if a read from the pty has observed that every process
+    /// holding the other end of the pty has closed it. See
+    /// [`crate::Pty::is_hung_up`].
+    #[must_use]
+    pub fn is_hung_up(&self) -> bool {
+        self.0.pt.is_hung_up()
+    }
+
+    /// Returns a future which resolves once every process holding the other
+    /// end of the pty has closed it, without reading any data from the pty.
+    /// See [`crate::Pty::hangup`].
+    ///
+    /// # Errors
+    /// The returned future resolves to an error if we were unable to
+    /// duplicate the pty file descriptor or register it with the tokio
+    /// reactor.
+    pub fn hangup(
+        &self,
+    ) -> impl std::future::Future<Output = crate::Result<()>> + Send + 'static
+    {
+        crate::pty::hangup(&self.0.pt)
+    }
+
+    /// Reads from the pty into `buf`, returning the number of bytes read
+    /// along with the buffer. See
+    /// [`tokio_uring::fs::File::read_at`].
+    pub async fn read<T: tokio_uring::buf::BoundedBufMut>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<usize, T> {
+        self.0.read(buf).await
+    }
+
+    /// Like [`read`](Self::read), but using a buffer registered with the
+    /// kernel. See [`tokio_uring::fs::File::read_fixed_at`].
+    pub async fn read_fixed<T>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<usize, T>
+    where
+        T: tokio_uring::buf::BoundedBufMut<
+            BufMut = tokio_uring::buf::fixed::FixedBuf,
+        >,
+    {
+        self.0.read_fixed(buf).await
+    }
+
+    /// Writes some prefix of `buf` to the pty, returning the number of bytes
+    /// written along with the buffer. See
+    /// [`tokio_uring::fs::File::write_at`].
+    pub async fn write<T: tokio_uring::buf::BoundedBuf>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<usize, T> {
+        self.0.write(buf).await
+    }
+
+    /// Writes all of `buf` to the pty. See
+    /// [`tokio_uring::fs::File::write_all_at`].
+    pub async fn write_all<T: tokio_uring::buf::BoundedBuf>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<(), T> {
+        self.0.write_all(buf).await
+    }
+
+    /// Like [`write`](Self::write), but using a buffer registered with the
+    /// kernel. See [`tokio_uring::fs::File::write_fixed_at`].
+    pub async fn write_fixed<T>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<usize, T>
+    where
+        T: tokio_uring::buf::BoundedBuf<
+            Buf = tokio_uring::buf::fixed::FixedBuf,
+        >,
+    {
+        self.0.write_fixed(buf).await
+    }
+
+    /// Like [`write_all`](Self::write_all), but using a buffer registered
+    /// with the kernel. See
+    /// [`tokio_uring::fs::File::write_fixed_all_at`].
+    pub async fn write_fixed_all<T>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<(), T>
+    where
+        T: tokio_uring::buf::BoundedBuf<
+            Buf = tokio_uring::buf::fixed::FixedBuf,
+        >,
+    {
+        self.0.write_fixed_all(buf).await
+    }
+
+    /// Splits a `Pty` into a read half and a write half, which can be used to
+    /// read from and write to the pty concurrently. Does not allocate, but
+    /// the returned halves cannot be moved to independent tasks.
+    pub fn split(&mut self) -> (ReadPty<'_>, WritePty<'_>) {
+        (ReadPty(&self.0), WritePty(&self.0))
+    }
+
+    /// Splits a `Pty` into a read half and a write half, which can be used to
+    /// read from and write to the pty concurrently. This method requires an
+    /// allocation, but the returned halves can be moved to independent tasks
+    /// (on the same thread, since `tokio_uring` is single threaded). The
+    /// original `Pty` instance can be recovered via the
+    /// [`OwnedReadPty::unsplit`] method.
+    #[must_use]
+    pub fn into_split(self) -> (OwnedReadPty, OwnedWritePty) {
+        let Self(pt) = self;
+        let read_pt = std::rc::Rc::new(pt);
+        let write_pt = std::rc::Rc::clone(&read_pt);
+        (OwnedReadPty(read_pt), OwnedWritePty(write_pt))
+    }
+
+    /// Attaches a [`Tap`](crate::Tap), which will observe all data read from
+    /// and written to the pty, through this handle or any other handle to
+    /// the same pty (such as the halves returned by splitting it). Returns
+    /// an id which can be passed to [`remove_tap`](Self::remove_tap).
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.0.pt.add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) which was previously attached to the
+    /// pty through any handle. Returns false if the tap was not attached.
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.0.pt.remove_tap(id)
+    }
+
+    /// Starts collecting metrics for the pty, and returns a handle for
+    /// querying them. See
+    /// [`blocking::Pty::enable_metrics`](crate::blocking::Pty::enable_metrics).
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.0.pt.enable_metrics()
+    }
+}
+
+impl std::os::fd::AsFd for Pty {
+    fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        self.0.pt.as_fd()
+    }
+}
+
+impl std::os::fd::AsRawFd for Pty {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.0.pt.as_raw_fd()
+    }
+}
+
+/// Borrowed read half of a [`Pty`]
+pub struct ReadPty<'a>(&'a UringPty);
+
+impl ReadPty<'_> {
+    /// Controls whether reading from the pty after every process holding the
+    /// other end of the pty has closed it reports end of file. See
+    /// [`Pty::set_hangup_as_eof`].
+    pub fn set_hangup_as_eof(&self, enabled: bool) {
+        self.0.pt.set_hangup_as_eof(enabled);
+    }
+
+    /// Returns true if a read from the pty has observed that every process
+    /// holding the other end of the pty has closed it. See
+    /// [`Pty::is_hung_up`].
+    #[must_use]
+    pub fn is_hung_up(&self) -> bool {
+        self.0.pt.is_hung_up()
+    }
+
+    /// See [`Pty::read`].
+    pub async fn read<T: tokio_uring::buf::BoundedBufMut>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<usize, T> {
+        self.0.read(buf).await
+    }
+
+    /// See [`Pty::read_fixed`].
+    pub async fn read_fixed<T>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<usize, T>
+    where
+        T: tokio_uring::buf::BoundedBufMut<
+            BufMut = tokio_uring::buf::fixed::FixedBuf,
+        >,
+    {
+        self.0.read_fixed(buf).await
+    }
+
+    /// Attaches a [`Tap`](crate::Tap) to the pty. See [`Pty::add_tap`].
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.0.pt.add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) from the pty. See
+    /// [`Pty::remove_tap`].
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.0.pt.remove_tap(id)
+    }
+
+    /// Starts collecting metrics for the pty, and returns a handle for
+    /// querying them. See [`Pty::enable_metrics`].
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.0.pt.enable_metrics()
+    }
+}
+
+/// Borrowed write half of a [`Pty`]
+pub struct WritePty<'a>(&'a UringPty);
+
+impl WritePty<'_> {
+    /// Change the terminal size associated with the pty.
+    ///
+    /// # Errors
+    /// Returns an error if we were unable to set the terminal size.
+    pub fn resize(&self, size: crate::Size) -> crate::Result<()> {
+        self.0.pt.set_term_size(size)
+    }
+
+    /// See [`Pty::write`].
+    pub async fn write<T: tokio_uring::buf::BoundedBuf>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<usize, T> {
+        self.0.write(buf).await
+    }
+
+    /// See [`Pty::write_all`].
+    pub async fn write_all<T: tokio_uring::buf::BoundedBuf>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<(), T> {
+        self.0.write_all(buf).await
+    }
+
+    /// See [`Pty::write_fixed`].
+    pub async fn write_fixed<T>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<usize, T>
+    where
+        T: tokio_uring::buf::BoundedBuf<
+            Buf = tokio_uring::buf::fixed::FixedBuf,
+        >,
+    {
+        self.0.write_fixed(buf).await
+    }
+
+    /// See [`Pty::write_fixed_all`].
+    pub async fn write_fixed_all<T>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<(), T>
+    where
+        T: tokio_uring::buf::BoundedBuf<
+            Buf = tokio_uring::buf::fixed::FixedBuf,
+        >,
+    {
+        self.0.write_fixed_all(buf).await
+    }
+
+    /// Attaches a [`Tap`](crate::Tap) to the pty. See [`Pty::add_tap`].
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.0.pt.add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) from the pty. See
+    /// [`Pty::remove_tap`].
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.0.pt.remove_tap(id)
+    }
+
+    /// Starts collecting metrics for the pty, and returns a handle for
+    /// querying them. See [`Pty::enable_metrics`].
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.0.pt.enable_metrics()
+    }
+}
+
+/// Owned read half of a [`Pty`]
+pub struct OwnedReadPty(std::rc::Rc<UringPty>);
+
+impl OwnedReadPty {
+    /// Attempt to join the two halves of a `Pty` back into a single instance.
+    /// The two halves must have originated from calling
+    /// [`into_split`](Pty::into_split) on a single instance.
+    ///
+    /// # Errors
+    /// Returns an error if the two halves came from different [`Pty`]
+    /// instances. The mismatched halves are returned as part of the error.
+    /// Unlike the other pty types, this doesn't return a [`crate::Error`],
+    /// since the halves can't be sent between threads.
+    pub fn unsplit(
+        self,
+        write_half: OwnedWritePty,
+    ) -> Result<Pty, crate::UnsplitError<Self, OwnedWritePty>> {
+        let Self(read_pt) = self;
+        let OwnedWritePty(write_pt) = write_half;
+        if std::rc::Rc::ptr_eq(&read_pt, &write_pt) {
+            drop(write_pt);
+            Ok(Pty(std::rc::Rc::try_unwrap(read_pt)
+                // it shouldn't be possible for more than two references to
+                // the same pty to exist
+                .unwrap_or_else(|_| unreachable!())))
+        } else {
+            Err(crate::UnsplitError(Self(read_pt), OwnedWritePty(write_pt)))
+        }
+    }
+
+    /// Controls whether reading from the pty after every process holding the
+    /// other end of the pty has closed it reports end of file. See
+    /// [`Pty::set_hangup_as_eof`].
+    pub fn set_hangup_as_eof(&self, enabled: bool) {
+        self.0.pt.set_hangup_as_eof(enabled);
+    }
+
+    /// Returns true if a read from the pty has observed that every process
+    /// holding the other end of the pty has closed it. See
+    /// [`Pty::is_hung_up`].
+    #[must_use]
+    pub fn is_hung_up(&self) -> bool {
+        self.0.pt.is_hung_up()
+    }
+
+    /// See [`Pty::read`].
+    pub async fn read<T: tokio_uring::buf::BoundedBufMut>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<usize, T> {
+        self.0.read(buf).await
+    }
+
+    /// See [`Pty::read_fixed`].
+    pub async fn read_fixed<T>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<usize, T>
+    where
+        T: tokio_uring::buf::BoundedBufMut<
+            BufMut = tokio_uring::buf::fixed::FixedBuf,
+        >,
+    {
+        self.0.read_fixed(buf).await
+    }
+
+    /// Attaches a [`Tap`](crate::Tap) to the pty. See [`Pty::add_tap`].
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.0.pt.add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) from the pty. See
+    /// [`Pty::remove_tap`].
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.0.pt.remove_tap(id)
+    }
+
+    /// Starts collecting metrics for the pty, and returns a handle for
+    /// querying them. See [`Pty::enable_metrics`].
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.0.pt.enable_metrics()
+    }
+}
+
+/// Owned write half of a [`Pty`]
+pub struct OwnedWritePty(std::rc::Rc<UringPty>);
+
+impl OwnedWritePty {
+    /// Change the terminal size associated with the pty.
+    ///
+    /// # Errors
+    /// Returns an error if we were unable to set the terminal size.
+    pub fn resize(&self, size: crate::Size) -> crate::Result<()> {
+        self.0.pt.set_term_size(size)
+    }
+
+    /// See [`Pty::write`].
+    pub async fn write<T: tokio_uring::buf::BoundedBuf>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<usize, T> {
+        self.0.write(buf).await
+    }
+
+    /// See [`Pty::write_all`].
+    pub async fn write_all<T: tokio_uring::buf::BoundedBuf>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<(), T> {
+        self.0.write_all(buf).await
+    }
+
+    /// See [`Pty::write_fixed`].
+    pub async fn write_fixed<T>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<usize, T>
+    where
+        T: tokio_uring::buf::BoundedBuf<
+            Buf = tokio_uring::buf::fixed::FixedBuf,
+        >,
+    {
+        self.0.write_fixed(buf).await
+    }
+
+    /// See [`Pty::write_fixed_all`].
+    pub async fn write_fixed_all<T>(
+        &self,
+        buf: T,
+    ) -> tokio_uring::BufResult<(), T>
+    where
+        T: tokio_uring::buf::BoundedBuf<
+            Buf = tokio_uring::buf::fixed::FixedBuf,
+        >,
+    {
+        self.0.write_fixed_all(buf).await
+    }
+
+    /// Attaches a [`Tap`](crate::Tap) to the pty. See [`Pty::add_tap`].
+    pub fn add_tap(
+        &self,
+        tap: std::sync::Arc<dyn crate::Tap>,
+    ) -> crate::TapId {
+        self.0.pt.add_tap(tap)
+    }
+
+    /// Detaches a [`Tap`](crate::Tap) from the pty. See
+    /// [`Pty::remove_tap`].
+    #[must_use]
+    pub fn remove_tap(&self, id: crate::TapId) -> bool {
+        self.0.pt.remove_tap(id)
+    }
+
+    /// Starts collecting metrics for the pty, and returns a handle for
+    /// querying them. See [`Pty::enable_metrics`].
+    #[must_use]
+    pub fn enable_metrics(&self) -> crate::MetricsHandle {
+        self.0.pt.enable_metrics()
+    }
+}
@@ -0,0 +1,207 @@
+mod helpers;
+
+// the tests which switch users need privileges, so they pass without
+// doing anything unless run as root
+
+fn is_root() -> bool {
+    unsafe { libc::geteuid() == 0 }
+}
+
+struct Passwd {
+    name: String,
+    uid: u32,
+    gid: u32,
+    dir: String,
+    shell: String,
+}
+
+// the expected values come from the passwd database, since they differ
+// between systems
+fn nobody() -> Passwd {
+    let string = |ptr| {
+        unsafe { std::ffi::CStr::from_ptr(ptr) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    };
+    let pwd = unsafe { libc::getpwnam(c"nobody".as_ptr()) };
+    assert!(!pwd.is_null());
+    let pwd = unsafe { &*pwd };
+    Passwd {
+        name: string(pwd.pw_name),
+        uid: pwd.pw_uid,
+        gid: pwd.pw_gid,
+        dir: string(pwd.pw_dir),
+        shell: string(pwd.pw_shell),
+    }
+}
+
+fn tty_gid(passwd: &Passwd) -> u32 {
+    let grp = unsafe { libc::getgrnam(c"tty".as_ptr()) };
+    if grp.is_null() {
+        passwd.gid
+    } else {
+        unsafe { (*grp).gr_gid }
+    }
+}
+
+#[test]
+fn test_as_user() {
+    if !is_root() {
+        return;
+    }
+    let passwd = nobody();
+    let groups = std::process::Command::new("id")
+        .args(["-G", &passwd.name])
+        .output()
+        .unwrap();
+    let groups = String::from_utf8(groups.stdout).unwrap();
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut cmd = pty_process::blocking::Command::new("sh");
+    cmd.arg("-c")
+        .arg(
+            "id -u; id -g; id -G; echo $HOME $USER $LOGNAME; \
+             stat -c '%u %g %a' $(tty)",
+        )
+        .as_user("nobody");
+    let mut child = cmd.spawn(&pts).unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), format!("{}\r\n", passwd.uid));
+    assert_eq!(output.next().unwrap(), format!("{}\r\n", passwd.gid));
+    assert_eq!(output.next().unwrap(), groups.replace('\n', "\r\n"));
+    assert_eq!(
+        output.next().unwrap(),
+        format!("{} {} {}\r\n", passwd.dir, passwd.name, passwd.name)
+    );
+    let mode = if tty_gid(&passwd) == passwd.gid {
+        600
+    } else {
+        620
+    };
+    assert_eq!(
+        output.next().unwrap(),
+        format!("{} {} {mode}\r\n", passwd.uid, tty_gid(&passwd))
+    );
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn test_as_user_uid() {
+    if !is_root() {
+        return;
+    }
+    let passwd = nobody();
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut cmd = pty_process::blocking::Command::new("sh");
+    cmd.arg("-c").arg("id -un; echo $SHELL").as_user(passwd.uid);
+    let mut child = cmd.spawn(&pts).unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), format!("{}\r\n", passwd.name));
+    assert_eq!(output.next().unwrap(), format!("{}\r\n", passwd.shell));
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn test_as_user_env_override() {
+    if !is_root() {
+        return;
+    }
+    let mut cmd = pty_process::blocking::Command::new("sh");
+    cmd.arg("-c")
+        .arg("echo $HOME; echo ${USER-unset}")
+        .env("HOME", "/tmp")
+        .as_user("nobody")
+        .env_remove("USER");
+
+    for _ in 0..2 {
+        let pty = pty_process::blocking::Pty::new().unwrap();
+        let pts = pty.pts().unwrap();
+        pty.resize(pty_process::Size::new(24, 80)).unwrap();
+        let mut child = cmd.spawn(&pts).unwrap();
+
+        let mut output = helpers::output(&pty);
+        assert_eq!(output.next().unwrap(), "/tmp\r\n");
+        assert_eq!(output.next().unwrap(), "unset\r\n");
+        assert!(child.wait().unwrap().success());
+    }
+}
+
+#[test]
+fn test_as_user_spawn_failure() {
+    if !is_root() {
+        return;
+    }
+    use std::os::fd::AsRawFd as _;
+    use std::os::unix::fs::MetadataExt as _;
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let mut buf = [0; 64];
+    assert_eq!(
+        unsafe { libc::ptsname_r(pty.as_raw_fd(), buf.as_mut_ptr(), 64) },
+        0
+    );
+    let path = unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) }
+        .to_str()
+        .unwrap()
+        .to_string();
+    let before = std::fs::metadata(&path).unwrap();
+
+    let err = pty_process::blocking::Command::new("/nonexistent")
+        .as_user("nobody")
+        .spawn(&pts)
+        .unwrap_err();
+    assert_eq!(err.operation(), Some(pty_process::Operation::Spawn));
+
+    // the pts is given back when spawning fails
+    let after = std::fs::metadata(&path).unwrap();
+    assert_eq!(after.uid(), before.uid());
+    assert_eq!(after.gid(), before.gid());
+    assert_eq!(after.mode(), before.mode());
+}
+
+#[test]
+fn test_as_user_unknown() {
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let err = pty_process::blocking::Command::new("true")
+        .as_user("pty-process-nonexistent-user")
+        .spawn(&pts)
+        .unwrap_err();
+    assert_eq!(err.operation(), Some(pty_process::Operation::LookupUser));
+    assert_eq!(err.pid(), None);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_as_user_async() {
+    if !is_root() {
+        return;
+    }
+    use futures::stream::StreamExt as _;
+
+    let passwd = nobody();
+
+    let pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("sh")
+        .arg("-c")
+        .arg("id -un; echo $HOME")
+        .as_user("nobody")
+        .spawn(&pts)
+        .unwrap();
+
+    let mut output = helpers::output_async(pty);
+    assert_eq!(output.next().await.unwrap(), format!("{}\r\n", passwd.name));
+    assert_eq!(output.next().await.unwrap(), format!("{}\r\n", passwd.dir));
+    assert!(child.wait().await.unwrap().success());
+}
@@ -0,0 +1,226 @@
+mod helpers;
+
+#[cfg(feature = "async-io")]
+#[test]
+fn test_cat_async_io() {
+    use futures::io::AsyncWriteExt as _;
+    use futures::stream::StreamExt as _;
+
+    async_io::block_on(async {
+        let mut pty = pty_process::async_io::Pty::new().unwrap();
+        let pts = pty.pts().unwrap();
+        pty.resize(pty_process::Size::new(24, 80)).unwrap();
+        let mut child = pty_process::async_io::Command::new("cat")
+            .spawn(&pts)
+            .unwrap();
+
+        let (pty_r, mut pty_w) = pty.split();
+
+        pty_w.write_all(b"foo\n").await.unwrap();
+
+        let mut output = helpers::output_async_io(pty_r);
+        assert_eq!(output.next().await.unwrap(), "foo\r\n");
+        assert_eq!(output.next().await.unwrap(), "foo\r\n");
+
+        pty_w.write_all(&[4u8]).await.unwrap();
+        let status = child.status().await.unwrap();
+        assert_eq!(status.code().unwrap(), 0);
+    });
+}
+
+#[cfg(feature = "async-io")]
+#[test]
+fn test_close_async_io() {
+    use futures::io::AsyncWriteExt as _;
+
+    async_io::block_on(async {
+        let pty = pty_process::async_io::Pty::new().unwrap();
+        let pts = pty.pts().unwrap();
+        pty.resize(pty_process::Size::new(24, 80)).unwrap();
+        pty.set_eof_on_shutdown(true);
+        let mut child = pty_process::async_io::Command::new("cat")
+            .spawn(&pts)
+            .unwrap();
+
+        let (_pty_r, mut pty_w) = pty.into_split();
+        pty_w.write_all(b"foo").await.unwrap();
+        pty_w.close().await.unwrap();
+
+        let status = child.status().await.unwrap();
+        assert_eq!(status.code().unwrap(), 0);
+    });
+}
+
+#[cfg(feature = "async-io")]
+#[test]
+fn test_into_split_async_io() {
+    use futures::io::AsyncWriteExt as _;
+    use futures::stream::StreamExt as _;
+
+    async_io::block_on(async {
+        let pty = pty_process::async_io::Pty::new().unwrap();
+        let pts = pty.pts().unwrap();
+        pty.resize(pty_process::Size::new(24, 80)).unwrap();
+        let mut cmd = pty_process::async_io::Command::new("perl");
+        cmd.args(["-plE", "BEGIN { $SIG{WINCH} = sub { say 'WINCH' } }"]);
+        let mut child = cmd.spawn(&pts).unwrap();
+
+        let (pty_r, mut pty_w) = pty.into_split();
+        let mut output = helpers::output_async_io(pty_r);
+
+        pty_w.write_all(b"foo\n").await.unwrap();
+        assert_eq!(output.next().await.unwrap(), "foo\r\n");
+        assert_eq!(output.next().await.unwrap(), "foo\r\n");
+
+        pty_w.resize(pty_process::Size::new(25, 80)).unwrap();
+        assert_eq!(output.next().await.unwrap(), "WINCH\r\n");
+
+        pty_w.write_all(&[4u8]).await.unwrap();
+        let status = child.status().await.unwrap();
+        assert_eq!(status.code().unwrap(), 0);
+    });
+}
+
+#[cfg(feature = "async-io")]
+#[test]
+fn test_into_split_error_async_io() {
+    let pty1 = pty_process::async_io::Pty::new().unwrap();
+    let pty2 = pty_process::async_io::Pty::new().unwrap();
+
+    let (pty1_r, pty1_w) = pty1.into_split();
+    let (pty2_r, pty2_w) = pty2.into_split();
+
+    let (pty1_r, pty2_w) = if let Err(pty_process::UnsplitError(r, w)) =
+        pty1_r.unsplit(pty2_w)
+    {
+        (r, w)
+    } else {
+        panic!("fail");
+    };
+    let (pty2_r, pty1_w) = if let Err(pty_process::UnsplitError(r, w)) =
+        pty2_r.unsplit(pty1_w)
+    {
+        (r, w)
+    } else {
+        panic!("fail");
+    };
+
+    let _pty1 = pty1_r.unsplit(pty1_w).unwrap();
+    let _pty2 = pty2_r.unsplit(pty2_w).unwrap();
+}
+
+#[cfg(feature = "async-io")]
+#[test]
+fn test_session_leader_async_io() {
+    use futures::stream::StreamExt as _;
+
+    async_io::block_on(async {
+        let mut pty = pty_process::async_io::Pty::new().unwrap();
+        let pts = pty.pts().unwrap();
+        pty.resize(pty_process::Size::new(24, 80)).unwrap();
+        let mut cmd = pty_process::async_io::Command::new("perl");
+        cmd.arg(
+            "-Eopen my $fh, '<', '/dev/tty' or die; \
+                if (-t $fh) { say 'true' } else { say 'false' }; \
+                say getpgrp == $$ ? 'leader' : 'not leader'",
+        );
+        let (pty_r, _) = pty.split();
+        let mut output = helpers::output_async_io(pty_r);
+
+        for _ in 0..2 {
+            let mut child = cmd.spawn(&pts).unwrap();
+            assert_eq!(output.next().await.unwrap(), "true\r\n");
+            assert_eq!(output.next().await.unwrap(), "leader\r\n");
+            let status = child.status().await.unwrap();
+            assert_eq!(status.code().unwrap(), 0);
+        }
+    });
+}
+
+#[cfg(feature = "async-io")]
+#[test]
+fn test_pre_exec_async_io() {
+    let pty = pty_process::async_io::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let mut cmd = pty_process::async_io::Command::new("true");
+    unsafe {
+        cmd.pre_exec(|| {
+            Err(std::io::Error::from_raw_os_error(nix::libc::EPERM))
+        });
+    }
+    let err = cmd.spawn(&pts).unwrap_err();
+    assert_eq!(err.operation(), Some(pty_process::Operation::PreExec));
+    if let pty_process::Error::Operation { source, .. } = err {
+        assert_eq!(source.raw_os_error(), Some(nix::libc::EPERM));
+    } else {
+        panic!("unexpected error {err}");
+    }
+}
+
+#[cfg(feature = "async-io")]
+#[test]
+fn test_cat_vectored_async_io() {
+    use futures::io::AsyncWriteExt as _;
+    use futures::stream::StreamExt as _;
+
+    async_io::block_on(async {
+        let mut pty = pty_process::async_io::Pty::new().unwrap();
+        let pts = pty.pts().unwrap();
+        pty.resize(pty_process::Size::new(24, 80)).unwrap();
+        let mut child = pty_process::async_io::Command::new("cat")
+            .spawn(&pts)
+            .unwrap();
+
+        let (pty_r, mut pty_w) = pty.split();
+        let bytes = pty_w
+            .write_vectored(&[
+                std::io::IoSlice::new(b"fo"),
+                std::io::IoSlice::new(b"o\n"),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(bytes, 4);
+
+        let mut output = helpers::output_async_io(pty_r);
+        assert_eq!(output.next().await.unwrap(), "foo\r\n");
+        assert_eq!(output.next().await.unwrap(), "foo\r\n");
+
+        pty_w.write_all(&[4u8]).await.unwrap();
+        let status = child.status().await.unwrap();
+        assert_eq!(status.code().unwrap(), 0);
+    });
+}
+
+#[cfg(feature = "async-io")]
+#[test]
+fn test_hangup_async_io() {
+    use futures::io::AsyncWriteExt as _;
+
+    async_io::block_on(async {
+        let pty = pty_process::async_io::Pty::new().unwrap();
+        let pts = pty.pts().unwrap();
+        pty.resize(pty_process::Size::new(24, 80)).unwrap();
+        let mut child = pty_process::async_io::Command::new("cat")
+            .spawn(&pts)
+            .unwrap();
+        drop(pts);
+
+        let mut hangup = std::pin::pin!(pty.hangup());
+        let (_pty_r, mut pty_w) = pty.into_split();
+
+        pty_w.write_all(b"foo\n").await.unwrap();
+        async_io::Timer::after(std::time::Duration::from_millis(100)).await;
+        assert!(futures::poll!(hangup.as_mut()).is_pending());
+
+        pty_w.write_all(&[4u8]).await.unwrap();
+        let timeout =
+            async_io::Timer::after(std::time::Duration::from_secs(5));
+        match futures::future::select(hangup, timeout).await {
+            futures::future::Either::Left((res, _)) => res.unwrap(),
+            futures::future::Either::Right(_) => panic!("timed out"),
+        }
+
+        let status = child.status().await.unwrap();
+        assert_eq!(status.code().unwrap(), 0);
+    });
+}
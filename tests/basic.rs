@@ -70,3 +70,150 @@ async fn test_yes_async() {
 
     child.kill().await.unwrap()
 }
+
+#[test]
+fn test_cat_vectored_blocking() {
+    use std::io::{Read as _, Write as _};
+
+    let mut pty = pty_process::blocking::Pty::new().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("cat")
+        .spawn(&pty.pts().unwrap())
+        .unwrap();
+
+    let bytes = pty
+        .write_vectored(&[
+            std::io::IoSlice::new(b"fo"),
+            std::io::IoSlice::new(b"o\n"),
+        ])
+        .unwrap();
+    assert_eq!(bytes, 4);
+
+    let mut output = vec![];
+    while output.len() < 10 {
+        let mut buf1 = [0u8; 3];
+        let mut buf2 = [0u8; 7];
+        nix::unistd::alarm::set(5);
+        let bytes = pty
+            .read_vectored(&mut [
+                std::io::IoSliceMut::new(&mut buf1),
+                std::io::IoSliceMut::new(&mut buf2),
+            ])
+            .unwrap();
+        nix::unistd::alarm::cancel();
+        let buf: Vec<u8> = buf1.iter().chain(buf2.iter()).copied().collect();
+        output.extend_from_slice(&buf[..bytes]);
+    }
+    assert_eq!(output, b"foo\r\nfoo\r\n");
+
+    pty.write_all(&[4u8]).unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_cat_vectored_async() {
+    use futures::stream::StreamExt as _;
+    use tokio::io::AsyncWriteExt as _;
+
+    let mut pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("cat").spawn(&pts).unwrap();
+
+    let (pty_r, mut pty_w) = pty.split();
+    assert!(tokio::io::AsyncWrite::is_write_vectored(&pty_w));
+
+    let bytes = pty_w
+        .write_vectored(&[
+            std::io::IoSlice::new(b"fo"),
+            std::io::IoSlice::new(b"o\n"),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(bytes, 4);
+
+    let mut output = helpers::output_async(pty_r);
+    assert_eq!(output.next().await.unwrap(), "foo\r\n");
+    assert_eq!(output.next().await.unwrap(), "foo\r\n");
+
+    pty_w.write_all(&[4u8]).await.unwrap();
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_yes_uninit_async() {
+    use tokio::io::AsyncReadExt as _;
+
+    let mut pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("yes").spawn(&pts).unwrap();
+
+    let mut buf = bytes::BytesMut::with_capacity(3);
+    let bytes = pty.read_buf(&mut buf).await.unwrap();
+    assert_eq!(bytes, buf.len());
+    assert!(b"y\r\n".starts_with(&buf));
+
+    child.kill().await.unwrap()
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_cat_shutdown_async() {
+    use futures::stream::StreamExt as _;
+    use tokio::io::AsyncWriteExt as _;
+
+    let mut pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    pty.set_eof_on_shutdown(true);
+    let mut child = pty_process::Command::new("cat").spawn(&pts).unwrap();
+
+    let (pty_r, mut pty_w) = pty.split();
+
+    pty_w.write_all(b"foo\n").await.unwrap();
+
+    let mut output = helpers::output_async(pty_r);
+    assert_eq!(output.next().await.unwrap(), "foo\r\n");
+    assert_eq!(output.next().await.unwrap(), "foo\r\n");
+
+    pty_w.shutdown().await.unwrap();
+    let status =
+        tokio::time::timeout(std::time::Duration::from_secs(5), child.wait())
+            .await
+            .unwrap()
+            .unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_cat_shutdown_partial_line_async() {
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+    let pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("cat").spawn(&pts).unwrap();
+
+    let (mut pty_r, mut pty_w) = pty.into_split();
+    pty_w.set_eof_on_shutdown(true);
+
+    pty_w.write_all(b"foo").await.unwrap();
+    pty_w.shutdown().await.unwrap();
+
+    let status =
+        tokio::time::timeout(std::time::Duration::from_secs(5), child.wait())
+            .await
+            .unwrap()
+            .unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    let mut buf = [0u8; 64];
+    let bytes = pty_r.read(&mut buf).await.unwrap();
+    assert!(buf[..bytes].starts_with(b"foo"));
+}
@@ -303,6 +303,271 @@ async fn test_session_leader_async() {
     assert_eq!(status.code().unwrap(), 0);
 }
 
+// prints the pid, process group id, session id, and controlling terminal
+// of the process
+const SESSION_SCRIPT: &str = "exec ps -o pid=,pgid=,sid=,tty= -p $$";
+
+fn session_info(line: &str) -> (i32, i32, i32, String) {
+    let fields: Vec<_> = line.split_whitespace().collect();
+    assert_eq!(fields.len(), 4, "{line}");
+    (
+        fields[0].parse().unwrap(),
+        fields[1].parse().unwrap(),
+        fields[2].parse().unwrap(),
+        fields[3].to_string(),
+    )
+}
+
+#[test]
+fn test_session_mode() {
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut output = helpers::output(&pty);
+
+    let mut child = pty_process::blocking::Command::new("sh")
+        .arg("-c")
+        .arg(SESSION_SCRIPT)
+        .session_mode(pty_process::SessionMode::NewSession)
+        .spawn(&pts)
+        .unwrap();
+    let (pid, pgid, sid, tty) = session_info(&output.next().unwrap());
+    assert_eq!(pid, i32::try_from(child.id()).unwrap());
+    assert_eq!(pgid, pid);
+    assert_eq!(sid, pid);
+    assert_ne!(tty, "?");
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    let mut child = pty_process::blocking::Command::new("sh")
+        .arg("-c")
+        .arg(SESSION_SCRIPT)
+        .session_mode(pty_process::SessionMode::NoControllingTerminal)
+        .spawn(&pts)
+        .unwrap();
+    let (pid, pgid, sid, tty) = session_info(&output.next().unwrap());
+    assert_eq!(pid, i32::try_from(child.id()).unwrap());
+    assert_eq!(pgid, pid);
+    assert_eq!(sid, pid);
+    assert_eq!(tty, "?");
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    let mut child = pty_process::blocking::Command::new("sh")
+        .arg("-c")
+        .arg(SESSION_SCRIPT)
+        .session_mode(pty_process::SessionMode::NewProcessGroup)
+        .spawn(&pts)
+        .unwrap();
+    let (pid, pgid, sid, _) = session_info(&output.next().unwrap());
+    assert_eq!(pid, i32::try_from(child.id()).unwrap());
+    assert_eq!(pgid, pid);
+    assert_eq!(sid, unsafe { nix::libc::getsid(0) });
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_session_mode_async() {
+    use futures::stream::StreamExt as _;
+
+    let mut pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let (pty_r, _) = pty.split();
+    let mut output = helpers::output_async(pty_r);
+
+    let mut child = pty_process::Command::new("sh")
+        .arg("-c")
+        .arg(SESSION_SCRIPT)
+        .session_mode(pty_process::SessionMode::NoControllingTerminal)
+        .spawn(&pts)
+        .unwrap();
+    let (pid, pgid, sid, tty) = session_info(&output.next().await.unwrap());
+    assert_eq!(pid, i32::try_from(child.id().unwrap()).unwrap());
+    assert_eq!(pgid, pid);
+    assert_eq!(sid, pid);
+    assert_eq!(tty, "?");
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    let mut child = pty_process::Command::new("sh")
+        .arg("-c")
+        .arg(SESSION_SCRIPT)
+        .session_mode(pty_process::SessionMode::NewProcessGroup)
+        .spawn(&pts)
+        .unwrap();
+    let (pid, pgid, sid, _) = session_info(&output.next().await.unwrap());
+    assert_eq!(pid, i32::try_from(child.id().unwrap()).unwrap());
+    assert_eq!(pgid, pid);
+    assert_eq!(sid, unsafe { nix::libc::getsid(0) });
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_hangup_eof() {
+    use std::io::Read as _;
+
+    let mut pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("echo")
+        .arg("foo")
+        .spawn(&pts)
+        .unwrap();
+    drop(pts);
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    let mut output = vec![];
+    nix::unistd::alarm::set(5);
+    pty.read_to_end(&mut output).unwrap();
+    nix::unistd::alarm::cancel();
+    assert_eq!(output, b"foo\r\n");
+    assert!(pty.is_hung_up());
+
+    let mut buf = [0u8; 16];
+    assert_eq!(pty.read(&mut buf).unwrap(), 0);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_hangup_eio() {
+    use std::io::Read as _;
+
+    let mut pty = pty_process::blocking::Pty::new().unwrap();
+    pty.set_hangup_as_eof(false);
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("true")
+        .spawn(&pts)
+        .unwrap();
+    drop(pts);
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    assert!(!pty.is_hung_up());
+    let mut buf = [0u8; 16];
+    let err = pty.read(&mut buf).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(nix::errno::Errno::EIO as i32));
+    assert!(pty.is_hung_up());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_hangup_eof_async() {
+    use tokio::io::AsyncReadExt as _;
+
+    let pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("echo")
+        .arg("foo")
+        .spawn(&pts)
+        .unwrap();
+    drop(pts);
+
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    let (mut pty_r, _pty_w) = pty.into_split();
+    let mut output = vec![];
+    tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        pty_r.read_to_end(&mut output),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(output, b"foo\r\n");
+    assert!(pty_r.is_hung_up());
+}
+
+#[test]
+fn test_wait_hangup() {
+    use std::io::Write as _;
+
+    let mut pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("cat")
+        .spawn(&pts)
+        .unwrap();
+    drop(pts);
+
+    assert!(!pty
+        .wait_hangup(Some(std::time::Duration::from_millis(100)))
+        .unwrap());
+
+    pty.write_all(&[4u8]).unwrap();
+    assert!(pty
+        .wait_hangup(Some(std::time::Duration::from_secs(5)))
+        .unwrap());
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_wait_hangup_split() {
+    use std::io::Write as _;
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("cat")
+        .spawn(&pts)
+        .unwrap();
+    drop(pts);
+
+    let (pty_r, mut pty_w) = pty.into_split();
+    assert!(!pty_r
+        .wait_hangup(Some(std::time::Duration::from_millis(100)))
+        .unwrap());
+
+    pty_w.write_all(&[4u8]).unwrap();
+    assert!(pty_r
+        .wait_hangup(Some(std::time::Duration::from_secs(5)))
+        .unwrap());
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_hangup_async() {
+    use tokio::io::AsyncWriteExt as _;
+
+    let pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("cat").spawn(&pts).unwrap();
+    drop(pts);
+
+    let hangup = pty.hangup();
+    let (_pty_r, mut pty_w) = pty.into_split();
+    let hangup = tokio::spawn(hangup);
+
+    pty_w.write_all(b"foo\n").await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert!(!hangup.is_finished());
+
+    pty_w.write_all(&[4u8]).await.unwrap();
+    tokio::time::timeout(std::time::Duration::from_secs(5), hangup)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
 fn pipe() -> (std::os::fd::OwnedFd, std::os::fd::OwnedFd) {
     use std::os::fd::FromRawFd as _;
 
@@ -0,0 +1,80 @@
+#[cfg(feature = "codec")]
+#[tokio::test]
+async fn test_stream_sink() {
+    use futures::sink::SinkExt as _;
+    use futures::stream::StreamExt as _;
+
+    let pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("cat").spawn(&pts).unwrap();
+    drop(pts);
+
+    let (pty_r, pty_w) = pty.into_split();
+    pty_w.set_eof_on_shutdown(true);
+    let mut stream = pty_r.into_stream(4);
+    let mut sink = pty_w.into_sink();
+
+    sink.send(bytes::Bytes::from_static(b"foo\n"))
+        .await
+        .unwrap();
+    let mut output = vec![];
+    while output.len() < 10 {
+        let chunk = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            stream.next(),
+        )
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+        assert!(chunk.len() <= 4);
+        output.extend_from_slice(&chunk);
+    }
+    assert_eq!(output, b"foo\r\nfoo\r\n");
+
+    futures::sink::SinkExt::<bytes::Bytes>::close(&mut sink)
+        .await
+        .unwrap();
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    assert!(tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        stream.next()
+    )
+    .await
+    .unwrap()
+    .is_none());
+}
+
+#[cfg(feature = "codec")]
+#[tokio::test]
+async fn test_framed() {
+    use futures::sink::SinkExt as _;
+    use futures::stream::StreamExt as _;
+
+    let pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("cat").spawn(&pts).unwrap();
+
+    let mut framed = pty.framed(tokio_util::codec::LinesCodec::new());
+
+    framed.send("foo").await.unwrap();
+    for _ in 0..2 {
+        let line = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            framed.next(),
+        )
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+        assert_eq!(line, "foo");
+    }
+
+    framed.send("\u{4}").await.unwrap();
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
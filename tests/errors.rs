@@ -0,0 +1,79 @@
+#[test]
+fn test_spawn_error() {
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let err = pty_process::blocking::Command::new("/nonexistent")
+        .spawn(&pts)
+        .unwrap_err();
+    assert_eq!(err.operation(), Some(pty_process::Operation::Spawn));
+    let path = err.pty().unwrap();
+    assert!(path.starts_with("/dev"));
+    assert_eq!(err.pid(), None);
+    assert!(std::error::Error::source(&err).is_some());
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "spawn failed (pty {}): No such file or directory (os error 2)",
+            path.display()
+        )
+    );
+}
+
+#[test]
+fn test_pre_exec_error() {
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let mut cmd = pty_process::blocking::Command::new("true");
+    unsafe {
+        cmd.pre_exec(|| {
+            Err(std::io::Error::from_raw_os_error(nix::libc::EPERM))
+        });
+    }
+    let err = cmd.spawn(&pts).unwrap_err();
+    assert_eq!(err.operation(), Some(pty_process::Operation::PreExec));
+    assert!(err.pty().is_some());
+    assert!(err.pid().is_some());
+    assert_ne!(err.pid(), Some(std::process::id()));
+    if let pty_process::Error::Operation { source, .. } = err {
+        assert_eq!(source.raw_os_error(), Some(nix::libc::EPERM));
+    } else {
+        panic!("unexpected error {err}");
+    }
+}
+
+#[test]
+fn test_session_leader_error() {
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let mut child = pty_process::blocking::Command::new("sleep")
+        .arg("5")
+        .spawn(&pts)
+        .unwrap();
+
+    // the pty is already the controlling terminal of the first child's
+    // session, so it can't become the controlling terminal of another
+    let err = pty_process::blocking::Command::new("true")
+        .spawn(&pts)
+        .unwrap_err();
+    assert_eq!(
+        err.operation(),
+        Some(pty_process::Operation::SetControllingTerminal)
+    );
+    assert!(err.pid().is_some());
+    assert!(err.to_string().starts_with("TIOCSCTTY failed (pty /dev/"));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_spawn_error_async() {
+    let pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let err = pty_process::Command::new("/nonexistent")
+        .spawn(&pts)
+        .unwrap_err();
+    assert_eq!(err.operation(), Some(pty_process::Operation::Spawn));
+    assert!(err.pty().is_some());
+}
@@ -0,0 +1,131 @@
+mod helpers;
+
+fn pipe() -> (std::fs::File, std::fs::File) {
+    let (r, w) = nix::unistd::pipe().unwrap();
+    // Safety: these fds were just created and are owned by nothing else
+    unsafe {
+        use std::os::fd::FromRawFd as _;
+        (std::fs::File::from_raw_fd(r), std::fs::File::from_raw_fd(w))
+    }
+}
+
+#[test]
+fn test_fd_map() {
+    use std::io::Read as _;
+
+    let (mut r1, w1) = pipe();
+    let (mut r2, w2) = pipe();
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut cmd = pty_process::blocking::Command::new("sh");
+    cmd.arg("-c")
+        .arg("echo foo >&3; echo bar >&7; echo baz")
+        .fd_map(3, w1)
+        .fd_map(7, w2);
+    let mut child = cmd.spawn(&pts).unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "baz\r\n");
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    // the command closes its copies of the write ends once the child has
+    // been spawned, so the pipes see end of file once the child exits
+    let mut buf = String::new();
+    r1.read_to_string(&mut buf).unwrap();
+    assert_eq!(buf, "foo\n");
+    buf.clear();
+    r2.read_to_string(&mut buf).unwrap();
+    assert_eq!(buf, "bar\n");
+}
+
+#[test]
+fn test_fd_map_overlapping() {
+    use std::io::Read as _;
+    use std::os::fd::AsRawFd as _;
+
+    let (mut r1, w1) = pipe();
+    let (mut r2, w2) = pipe();
+    let w1_fd = w1.as_raw_fd();
+    let w2_fd = w2.as_raw_fd();
+
+    // map each fd onto the other's number, which would clobber one of them
+    // if the mappings were applied naively
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let mut cmd = pty_process::blocking::Command::new("sh");
+    cmd.arg("-c")
+        .arg(format!("echo foo >&{w2_fd}; echo bar >&{w1_fd}"))
+        .fd_map(w2_fd, w1)
+        .fd_map(w1_fd, w2);
+    let mut child = cmd.spawn(&pts).unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    let mut buf = String::new();
+    r1.read_to_string(&mut buf).unwrap();
+    assert_eq!(buf, "foo\n");
+    buf.clear();
+    r2.read_to_string(&mut buf).unwrap();
+    assert_eq!(buf, "bar\n");
+}
+
+#[test]
+fn test_fd_map_once() {
+    let (_r, w) = pipe();
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let mut cmd = pty_process::blocking::Command::new("sh");
+    cmd.arg("-c").arg("test -e /dev/fd/57").fd_map(57, w);
+    let mut child = cmd.spawn(&pts).unwrap();
+    assert!(child.wait().unwrap().success());
+
+    // the fd was only mapped for the first child
+    let mut child = cmd.spawn(&pts).unwrap();
+    assert!(!child.wait().unwrap().success());
+}
+
+#[test]
+fn test_fd_map_error() {
+    let (_r, w) = pipe();
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let err = pty_process::blocking::Command::new("true")
+        .fd_map(-1, w)
+        .spawn(&pts)
+        .unwrap_err();
+    assert_eq!(err.operation(), Some(pty_process::Operation::FdMap));
+    assert!(err.pid().is_some());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_fd_map_async() {
+    use futures::stream::StreamExt as _;
+    use tokio::io::AsyncReadExt as _;
+
+    let (r, w) = pipe();
+
+    let pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut cmd = pty_process::Command::new("sh");
+    cmd.arg("-c").arg("echo foo >&3; echo bar").fd_map(3, w);
+    let mut child = cmd.spawn(&pts).unwrap();
+
+    let mut output = helpers::output_async(pty);
+    assert_eq!(output.next().await.unwrap(), "bar\r\n");
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    let mut buf = String::new();
+    tokio::fs::File::from_std(r)
+        .read_to_string(&mut buf)
+        .await
+        .unwrap();
+    assert_eq!(buf, "foo\n");
+}
@@ -51,3 +51,74 @@ pub fn output_async<'a>(
         ))
     }))
 }
+
+#[cfg(feature = "async-io")]
+pub fn output_async_io<'a>(
+    pty: impl futures::io::AsyncRead + std::marker::Unpin + 'a,
+) -> std::pin::Pin<Box<dyn futures::stream::Stream<Item = String> + 'a>> {
+    use futures::io::AsyncBufReadExt as _;
+
+    let pty = futures::io::BufReader::new(pty);
+    Box::pin(futures::stream::unfold(pty, |mut pty| async move {
+        let mut buf = vec![];
+        let read = Box::pin(pty.read_until(b'\n', &mut buf));
+        let timeout =
+            async_io::Timer::after(std::time::Duration::from_secs(5));
+        match futures::future::select(read, timeout).await {
+            futures::future::Either::Left((res, _)) => {
+                res.unwrap();
+            }
+            futures::future::Either::Right(_) => panic!("timed out"),
+        }
+        Some((std::string::String::from_utf8(buf).unwrap(), pty))
+    }))
+}
+
+const SEPARATE_PROCESS_ENV: &str = "PTY_PROCESS_TEST_SEPARATE_PROCESS";
+
+// tests which change process-wide state run the test function in a
+// separate copy of the test binary. tests which make a pty the controlling
+// terminal of the calling process also need to run in a new session, since
+// otherwise that would detach the test runner from its own terminal.
+pub fn in_separate_process(test: &str, f: impl FnOnce()) {
+    run_separately(test, false, f);
+}
+
+pub fn in_new_session(test: &str, f: impl FnOnce()) {
+    run_separately(test, true, f);
+}
+
+fn run_separately(test: &str, new_session: bool, f: impl FnOnce()) {
+    use std::os::unix::process::CommandExt as _;
+
+    if std::env::var_os(SEPARATE_PROCESS_ENV).is_some() {
+        // the test process may be the session leader for the pty, in which
+        // case it is sent SIGHUP when the pty is closed at the end of the
+        // test
+        // Safety: SigIgn is not a signal handler
+        unsafe {
+            nix::sys::signal::signal(
+                nix::sys::signal::Signal::SIGHUP,
+                nix::sys::signal::SigHandler::SigIgn,
+            )
+        }
+        .unwrap();
+        f();
+        return;
+    }
+
+    let mut cmd =
+        std::process::Command::new(std::env::current_exe().unwrap());
+    cmd.args(["--exact", test, "--nocapture"])
+        .env(SEPARATE_PROCESS_ENV, "1");
+    if new_session {
+        // Safety: setsid is async-signal-safe
+        unsafe {
+            cmd.pre_exec(|| {
+                nix::unistd::setsid()?;
+                Ok(())
+            })
+        };
+    }
+    assert!(cmd.status().unwrap().success());
+}
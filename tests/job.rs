@@ -0,0 +1,207 @@
+mod helpers;
+
+#[test]
+fn test_job_control() {
+    helpers::in_new_session("test_job_control", job_control);
+}
+
+fn job_control() {
+    use std::io::Write as _;
+
+    let mut pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let jobs = pty_process::blocking::JobControl::new(pts).unwrap();
+    let own_pgid = jobs.foreground_pgid().unwrap();
+
+    // reading from the pty in the background stops the job
+    let mut job = jobs
+        .spawn(
+            pty_process::blocking::Pipeline::new()
+                .cmd(pty_process::blocking::Command::new("cat")),
+            false,
+        )
+        .unwrap();
+    assert_eq!(job.pids().collect::<Vec<_>>(), vec![job.pgid()]);
+    assert_eq!(
+        jobs.wait(&mut job).unwrap(),
+        pty_process::blocking::JobStatus::Stopped(libc::SIGTTIN)
+    );
+
+    // continuing it in the foreground allows it to read
+    jobs.foreground(&mut job).unwrap();
+    assert_eq!(jobs.foreground_pgid().unwrap(), job.pgid());
+    pty.write_all(b"foo\n").unwrap();
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "foo\r\n");
+    assert_eq!(output.next().unwrap(), "foo\r\n");
+
+    // ^Z suspends the foreground job, and gives the terminal back
+    pty.write_all(b"\x1a").unwrap();
+    assert_eq!(
+        jobs.wait(&mut job).unwrap(),
+        pty_process::blocking::JobStatus::Stopped(libc::SIGTSTP)
+    );
+    assert_eq!(jobs.foreground_pgid().unwrap(), own_pgid);
+
+    jobs.background(&mut job).unwrap();
+    assert_eq!(
+        jobs.wait(&mut job).unwrap(),
+        pty_process::blocking::JobStatus::Stopped(libc::SIGTTIN)
+    );
+
+    nix::sys::signal::killpg(
+        nix::unistd::Pid::from_raw(job.pgid().try_into().unwrap()),
+        nix::sys::signal::Signal::SIGKILL,
+    )
+    .unwrap();
+    let pty_process::blocking::JobStatus::Exited(statuses) =
+        jobs.wait(&mut job).unwrap()
+    else {
+        panic!("job should have exited");
+    };
+    assert_eq!(statuses.len(), 1);
+    assert!(!statuses[0].success());
+}
+
+#[test]
+fn test_job_control_pipeline() {
+    helpers::in_new_session(
+        "test_job_control_pipeline",
+        job_control_pipeline,
+    );
+}
+
+fn job_control_pipeline() {
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let jobs = pty_process::blocking::JobControl::new(pts).unwrap();
+
+    let mut first = pty_process::blocking::Command::new("sh");
+    first.arg("-c").arg("echo foo");
+    let mut second = pty_process::blocking::Command::new("sh");
+    second.arg("-c").arg("tr a-z A-Z; exit 3");
+    let mut job = jobs
+        .spawn(
+            pty_process::blocking::Pipeline::new()
+                .cmd(first)
+                .cmd(second),
+            true,
+        )
+        .unwrap();
+    let pids: Vec<_> = job.pids().collect();
+    assert_eq!(pids.len(), 2);
+    assert_eq!(pids[0], job.pgid());
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "FOO\r\n");
+    let pty_process::blocking::JobStatus::Exited(statuses) =
+        jobs.wait(&mut job).unwrap()
+    else {
+        panic!("job should have exited");
+    };
+    assert_eq!(statuses[0].code(), Some(0));
+    assert_eq!(statuses[1].code(), Some(3));
+    assert!(!job.is_foreground());
+}
+
+#[test]
+fn test_job_control_pipe() {
+    helpers::in_new_session("test_job_control_pipe", job_control_pipe);
+}
+
+fn job_control_pipe() {
+    use std::io::Write as _;
+
+    let mut pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let jobs = pty_process::blocking::JobControl::new(pts).unwrap();
+    let own_pgid = jobs.foreground_pgid().unwrap();
+
+    let mut cmd = pty_process::blocking::Command::new("tr");
+    cmd.args(["a-z", "A-Z"]);
+    let mut job = jobs
+        .spawn(
+            pty_process::blocking::Pipeline::new()
+                .cmd(pty_process::blocking::Command::new("cat"))
+                .cmd(cmd),
+            true,
+        )
+        .unwrap();
+    assert_eq!(jobs.foreground_pgid().unwrap(), job.pgid());
+
+    // cat reads from the pty (which it can only do in the foreground), and
+    // tr reads cat's output from the pipe between them
+    pty.write_all(b"foo\n").unwrap();
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "foo\r\n");
+    assert_eq!(output.next().unwrap(), "FOO\r\n");
+
+    pty.write_all(b"\x04").unwrap();
+    let pty_process::blocking::JobStatus::Exited(statuses) =
+        jobs.wait(&mut job).unwrap()
+    else {
+        panic!("job should have exited");
+    };
+    assert_eq!(statuses.len(), 2);
+    assert!(statuses.iter().all(std::process::ExitStatus::success));
+    assert_eq!(jobs.foreground_pgid().unwrap(), own_pgid);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_job_control_async() {
+    helpers::in_new_session("test_job_control_async", || {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(job_control_async());
+    });
+}
+
+#[cfg(feature = "async")]
+async fn job_control_async() {
+    use futures::stream::StreamExt as _;
+    use tokio::io::AsyncWriteExt as _;
+
+    let mut pty = pty_process::Pty::new().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let jobs = pty_process::JobControl::new(pty.pts().unwrap()).unwrap();
+    let own_pgid = jobs.foreground_pgid().unwrap();
+
+    // reading from the pty in the background stops the job
+    let mut cmd = pty_process::Command::new("tr");
+    cmd.args(["a-z", "A-Z"]);
+    let mut job = jobs
+        .spawn(
+            pty_process::Pipeline::new()
+                .cmd(pty_process::Command::new("cat"))
+                .cmd(cmd),
+            false,
+        )
+        .unwrap();
+    assert_eq!(
+        jobs.wait(&mut job).await.unwrap(),
+        pty_process::JobStatus::Stopped(libc::SIGTTIN)
+    );
+
+    jobs.foreground(&mut job).unwrap();
+    assert_eq!(jobs.foreground_pgid().unwrap(), job.pgid());
+    let (pty_r, mut pty_w) = pty.split();
+    pty_w.write_all(b"foo\n").await.unwrap();
+    let mut output = helpers::output_async(pty_r);
+    assert_eq!(output.next().await.unwrap(), "foo\r\n");
+    assert_eq!(output.next().await.unwrap(), "FOO\r\n");
+
+    pty_w.write_all(b"\x04").await.unwrap();
+    let pty_process::JobStatus::Exited(statuses) =
+        jobs.wait(&mut job).await.unwrap()
+    else {
+        panic!("job should have exited");
+    };
+    assert_eq!(statuses.len(), 2);
+    assert!(statuses.iter().all(std::process::ExitStatus::success));
+    assert!(!job.is_foreground());
+    assert_eq!(jobs.foreground_pgid().unwrap(), own_pgid);
+}
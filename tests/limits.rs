@@ -0,0 +1,96 @@
+mod helpers;
+
+#[test]
+fn test_limits() {
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut cmd = pty_process::blocking::Command::new("sh");
+    cmd.arg("-c")
+        .arg("ulimit -n; ulimit -c; umask; nice")
+        .rlimit(pty_process::Resource::OpenFiles, Some(100), Some(200))
+        .rlimit(pty_process::Resource::Core, Some(0), Some(0))
+        .umask(0o027)
+        .nice(5);
+    let mut child = cmd.spawn(&pts).unwrap();
+    // the increment is relative to our own niceness, and the result is
+    // capped at 19
+    let nice =
+        (unsafe { libc::getpriority(libc::PRIO_PROCESS, 0) } + 5).min(19);
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "100\r\n");
+    assert_eq!(output.next().unwrap(), "0\r\n");
+    assert_eq!(output.next().unwrap(), "0027\r\n");
+    assert_eq!(output.next().unwrap(), format!("{nice}\r\n"));
+    assert!(child.wait().unwrap().success());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_io_priority() {
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let mut cmd = pty_process::blocking::Command::new("sleep");
+    cmd.arg("1000")
+        .io_priority(pty_process::IoPriority::BestEffort(5));
+    let mut child = cmd.spawn(&pts).unwrap();
+
+    // IOPRIO_WHO_PROCESS, and the best effort class (2) with level 5
+    let priority = unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_get,
+            1,
+            libc::c_int::try_from(child.id()).unwrap(),
+        )
+    };
+    assert_eq!(priority, (2 << 13) | 5);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+#[should_panic = "I/O priority levels range from 0 to 7"]
+fn test_io_priority_invalid() {
+    pty_process::blocking::Command::new("true")
+        .io_priority(pty_process::IoPriority::RealTime(8));
+}
+
+#[test]
+fn test_rlimit_error() {
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    // the soft limit can't be higher than the hard limit
+    let err = pty_process::blocking::Command::new("true")
+        .rlimit(pty_process::Resource::OpenFiles, Some(200), Some(100))
+        .spawn(&pts)
+        .unwrap_err();
+    assert_eq!(
+        err.operation(),
+        Some(pty_process::Operation::SetResourceLimit)
+    );
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_limits_async() {
+    use futures::stream::StreamExt as _;
+
+    let pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("sh")
+        .arg("-c")
+        .arg("ulimit -t; umask")
+        .rlimit(pty_process::Resource::Cpu, Some(10), Some(20))
+        .umask(0o077)
+        .spawn(&pts)
+        .unwrap();
+
+    let mut output = helpers::output_async(pty);
+    assert_eq!(output.next().await.unwrap(), "10\r\n");
+    assert_eq!(output.next().await.unwrap(), "0077\r\n");
+    assert!(child.wait().await.unwrap().success());
+}
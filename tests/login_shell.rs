@@ -0,0 +1,109 @@
+mod helpers;
+
+// login shells run the user's profile scripts first, which can print
+// anything, so the results are marked
+const SCRIPT: &str =
+    "echo \"result $0 $SHELL $(pwd) $HOME $USER $TERM $COLUMNS $LINES\"";
+
+fn result(line: &str) -> Option<Vec<String>> {
+    line.trim_end()
+        .strip_prefix("result ")
+        .map(|line| line.split(' ').map(ToString::to_string).collect())
+}
+
+#[test]
+fn test_login_shell() {
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let uid = unsafe { libc::geteuid() };
+    let mut cmd = pty_process::blocking::Command::login_shell(uid).unwrap();
+    let mut child = cmd.arg("-c").arg(SCRIPT).spawn(&pts).unwrap();
+
+    let output = helpers::output(&pty).find_map(|line| result(&line));
+    let output = output.unwrap();
+    let name = std::path::Path::new(&output[1]).file_name().unwrap();
+    assert_eq!(output[0], format!("-{}", name.to_str().unwrap()));
+    assert_eq!(output[2], output[3]);
+    assert!(!output[4].is_empty());
+    assert_eq!(output[5..], ["xterm-256color", "80", "24"]);
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn test_login_shell_term() {
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(50, 132)).unwrap();
+    let uid = unsafe { libc::geteuid() };
+    let mut cmd = pty_process::blocking::Command::login_shell(uid).unwrap();
+    let mut child = cmd
+        .arg("-c")
+        .arg("echo \"result $TERM $COLUMNS $LINES\"")
+        .env("TERM", "vt100")
+        .spawn(&pts)
+        .unwrap();
+
+    let output = helpers::output(&pty).find_map(|line| result(&line));
+    assert_eq!(output.unwrap(), ["vt100", "132", "50"]);
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn test_login_shell_env_override() {
+    let uid = unsafe { libc::geteuid() };
+    let mut cmd = pty_process::blocking::Command::login_shell(uid).unwrap();
+    cmd.arg("-c")
+        .arg("echo \"result $HOME $COLUMNS $LINES\"")
+        .env("COLUMNS", "100")
+        .env("HOME", "/tmp");
+
+    // the caller's variables are kept on every spawn, while the ones which
+    // weren't overridden are still updated from the pty
+    for rows in [24, 50] {
+        let pty = pty_process::blocking::Pty::new().unwrap();
+        let pts = pty.pts().unwrap();
+        pty.resize(pty_process::Size::new(rows, 80)).unwrap();
+        let mut child = cmd.spawn(&pts).unwrap();
+
+        let output = helpers::output(&pty).find_map(|line| result(&line));
+        assert_eq!(
+            output.unwrap(),
+            ["/tmp".to_string(), "100".to_string(), rows.to_string()]
+        );
+        assert!(child.wait().unwrap().success());
+    }
+}
+
+#[test]
+fn test_login_shell_unknown() {
+    let err = pty_process::blocking::Command::login_shell(
+        "pty-process-nonexistent-user",
+    )
+    .err()
+    .unwrap();
+    assert_eq!(err.operation(), Some(pty_process::Operation::LookupUser));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_login_shell_async() {
+    use futures::stream::StreamExt as _;
+
+    let pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let uid = unsafe { libc::geteuid() };
+    let mut child = pty_process::Command::login_shell(uid)
+        .unwrap()
+        .arg("-c")
+        .arg("echo \"result $TERM $COLUMNS $LINES\"")
+        .spawn(&pts)
+        .unwrap();
+
+    let mut output = helpers::output_async(pty)
+        .filter_map(|line| std::future::ready(result(&line)));
+    let output = output.next().await;
+    assert_eq!(output.unwrap(), ["xterm-256color", "80", "24"]);
+    assert!(child.wait().await.unwrap().success());
+}
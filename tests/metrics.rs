@@ -0,0 +1,165 @@
+mod helpers;
+
+#[test]
+fn test_metrics_blocking() {
+    use std::io::Write as _;
+
+    let mut pty = pty_process::blocking::Pty::new().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let handle = pty.enable_metrics();
+    let mut child = pty_process::blocking::Command::new("cat")
+        .spawn(&pty.pts().unwrap())
+        .unwrap();
+
+    let metrics = handle.snapshot();
+    assert_eq!(metrics.bytes_read(), 0);
+    assert_eq!(metrics.bytes_written(), 0);
+    assert!(metrics.last_read().is_none());
+    assert!(metrics.since_last_read().is_none());
+
+    let mut output = helpers::output(&pty);
+    (&pty).write_all(b"foo\n").unwrap();
+    assert_eq!(output.next().unwrap(), "foo\r\n");
+    assert_eq!(output.next().unwrap(), "foo\r\n");
+
+    pty.write_all(&[4u8]).unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    let metrics = handle.snapshot();
+    assert_eq!(metrics.bytes_read(), 10);
+    assert_eq!(metrics.bytes_written(), 5);
+    assert_eq!(metrics.writes(), 2);
+    assert!(metrics.reads() >= 1);
+    assert!(metrics.largest_read() >= 5);
+    assert!(metrics.largest_read() <= 10);
+    assert!(metrics.last_read().unwrap() <= std::time::Instant::now());
+    assert!(metrics.since_last_read().is_some());
+}
+
+#[test]
+fn test_metrics_split() {
+    use std::io::{Read as _, Write as _};
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let pts = pty.pts().unwrap();
+    let (mut read_pty, mut write_pty) = pty.into_split();
+    let mut child = pty_process::blocking::Command::new("cat")
+        .spawn(&pts)
+        .unwrap();
+
+    // metrics can be enabled through either half, and cover the whole pty
+    let handle = write_pty.enable_metrics();
+    write_pty.write_all(b"foo\n").unwrap();
+    read_pty.set_read_timeout(Some(std::time::Duration::from_secs(5)));
+    let mut buf = [0u8; 64];
+    let bytes = read_pty.read(&mut buf).unwrap();
+    assert!(bytes > 0);
+
+    let metrics = read_pty.enable_metrics().snapshot();
+    assert_eq!(metrics.bytes_written(), 4);
+    assert_eq!(metrics.bytes_read(), bytes as u64);
+    assert_eq!(handle.snapshot().bytes_read(), bytes as u64);
+
+    write_pty.write_all(&[4u8]).unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_metrics_write_stall() {
+    use std::io::{Read as _, Write as _};
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let pts = pty.pts().unwrap();
+    let handle = pty.enable_metrics();
+    let (mut read_pty, mut write_pty) = pty.into_split();
+    let mut child = pty_process::blocking::Command::new("sh")
+        .arg("-c")
+        .arg("stty raw -echo; echo ready; sleep 1; exec cat >/dev/null")
+        .spawn(&pts)
+        .unwrap();
+
+    read_pty.set_read_timeout(Some(std::time::Duration::from_secs(5)));
+    let mut buf = vec![];
+    while !String::from_utf8_lossy(&buf).contains("ready") {
+        let mut chunk = [0u8; 64];
+        let bytes = read_pty.read(&mut chunk).unwrap();
+        assert!(bytes > 0);
+        buf.extend_from_slice(&chunk[..bytes]);
+    }
+
+    // much larger than the kernel's pty buffer, so that the writes have to
+    // wait for the child to start reading
+    let data = vec![b'a'; 1024 * 1024];
+    write_pty.write_all(&data).unwrap();
+
+    let metrics = handle.snapshot();
+    assert_eq!(metrics.bytes_written(), 1024 * 1024);
+    assert!(metrics.write_stall() >= std::time::Duration::from_millis(100));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_metrics_async() {
+    use futures::stream::StreamExt as _;
+    use tokio::io::AsyncWriteExt as _;
+
+    let pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("cat").spawn(&pts).unwrap();
+    let handle = pty.enable_metrics();
+    let (pty_r, mut pty_w) = pty.into_split();
+
+    let mut output = helpers::output_async(pty_r);
+    pty_w.write_all(b"foo\n").await.unwrap();
+    assert_eq!(output.next().await.unwrap(), "foo\r\n");
+    assert_eq!(output.next().await.unwrap(), "foo\r\n");
+
+    pty_w.write_all(&[4u8]).await.unwrap();
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    let metrics = handle.snapshot();
+    assert_eq!(metrics.bytes_read(), 10);
+    assert_eq!(metrics.bytes_written(), 5);
+    assert!(metrics.last_read().is_some());
+}
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+#[test]
+fn test_metrics_uring() {
+    tokio_uring::start(async {
+        let pty = pty_process::uring::Pty::new().unwrap();
+        let pts = pty.pts().unwrap();
+        pty.resize(pty_process::Size::new(24, 80)).unwrap();
+        let handle = pty.enable_metrics();
+        let mut child = pty_process::Command::new("cat").spawn(&pts).unwrap();
+
+        let (res, _) = pty.write_all(b"foo\n".to_vec()).await;
+        res.unwrap();
+        let mut output = vec![];
+        while output.len() < 10 {
+            let (res, buf) = pty.read(Vec::with_capacity(1024)).await;
+            output.extend_from_slice(&buf[..res.unwrap()]);
+        }
+        assert_eq!(output, b"foo\r\nfoo\r\n");
+
+        let (res, _) = pty.write_all(vec![4u8]).await;
+        res.unwrap();
+        let status = child.wait().await.unwrap();
+        assert_eq!(status.code().unwrap(), 0);
+
+        let metrics = handle.snapshot();
+        assert_eq!(metrics.bytes_read(), 10);
+        assert_eq!(metrics.bytes_written(), 5);
+        assert_eq!(metrics.writes(), 2);
+        assert!(metrics.last_read().is_some());
+    });
+}
@@ -0,0 +1,145 @@
+#[test]
+fn test_nonblocking() {
+    use std::io::{Read as _, Write as _};
+
+    let mut pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    pty.set_nonblocking(true);
+    assert!(pty.is_nonblocking());
+    let mut child = pty_process::blocking::Command::new("cat")
+        .spawn(&pts)
+        .unwrap();
+
+    let mut buf = [0u8; 1024];
+    let err = pty.read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+
+    pty.set_nonblocking(false);
+    assert!(!pty.is_nonblocking());
+    pty.write_all(b"foo\n").unwrap();
+    let bytes = pty.read(&mut buf).unwrap();
+    assert!(b"foo\r\nfoo\r\n".starts_with(&buf[..bytes]));
+
+    pty.write_all(&[4u8]).unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[cfg(feature = "mio")]
+#[test]
+fn test_mio() {
+    use std::io::{Read as _, Write as _};
+
+    let mut pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    pty.set_nonblocking(true);
+    let mut child = pty_process::blocking::Command::new("cat")
+        .spawn(&pts)
+        .unwrap();
+
+    let mut poll = mio::Poll::new().unwrap();
+    let mut events = mio::Events::with_capacity(8);
+    poll.registry()
+        .register(&mut pty, mio::Token(0), mio::Interest::READABLE)
+        .unwrap();
+
+    pty.write_all(b"foo\n").unwrap();
+
+    let mut output = vec![];
+    let mut buf = [0u8; 1024];
+    while output.len() < 10 {
+        poll.poll(&mut events, Some(std::time::Duration::from_secs(5)))
+            .unwrap();
+        assert!(!events.is_empty(), "timed out");
+        loop {
+            match pty.read(&mut buf) {
+                Ok(bytes) => output.extend_from_slice(&buf[..bytes]),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    break;
+                }
+                Err(e) => panic!("{e}"),
+            }
+        }
+    }
+    assert_eq!(output, b"foo\r\nfoo\r\n");
+
+    poll.registry().deregister(&mut pty).unwrap();
+
+    pty.write_all(&[4u8]).unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_into_fd_blocking() {
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    pty.set_nonblocking(true);
+    let fd = std::os::fd::OwnedFd::from(pty);
+    let flags = nix::fcntl::fcntl(
+        std::os::fd::AsRawFd::as_raw_fd(&fd),
+        nix::fcntl::FcntlArg::F_GETFL,
+    )
+    .unwrap();
+    assert!(!nix::fcntl::OFlag::from_bits_truncate(flags)
+        .contains(nix::fcntl::OFlag::O_NONBLOCK));
+}
+
+#[cfg(feature = "mio")]
+#[test]
+fn test_mio_split() {
+    use std::io::{Read as _, Write as _};
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("cat")
+        .spawn(&pts)
+        .unwrap();
+    let (mut pty_r, mut pty_w) = pty.into_split();
+    pty_r.set_nonblocking(true);
+    assert!(pty_w.is_nonblocking());
+
+    let mut poll = mio::Poll::new().unwrap();
+    let mut events = mio::Events::with_capacity(8);
+    poll.registry()
+        .register(&mut pty_r, mio::Token(0), mio::Interest::READABLE)
+        .unwrap();
+
+    pty_w.write_all(b"foo\n").unwrap();
+
+    let mut output = vec![];
+    let mut buf = [0u8; 1024];
+    while output.len() < 10 {
+        poll.poll(&mut events, Some(std::time::Duration::from_secs(5)))
+            .unwrap();
+        assert!(!events.is_empty(), "timed out");
+        loop {
+            match pty_r.read(&mut buf) {
+                Ok(bytes) => output.extend_from_slice(&buf[..bytes]),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    break;
+                }
+                Err(e) => panic!("{e}"),
+            }
+        }
+    }
+    assert_eq!(output, b"foo\r\nfoo\r\n");
+
+    poll.registry().deregister(&mut pty_r).unwrap();
+    pty_r.set_nonblocking(false);
+    assert!(!pty_w.is_nonblocking());
+
+    pty_w.write_all(b"bar\n").unwrap();
+    let mut output = vec![];
+    while output.len() < 10 {
+        let bytes = pty_r.read(&mut buf).unwrap();
+        output.extend_from_slice(&buf[..bytes]);
+    }
+    assert_eq!(output, b"bar\r\nbar\r\n");
+
+    pty_w.write_all(&[4u8]).unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
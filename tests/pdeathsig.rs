@@ -0,0 +1,196 @@
+#![cfg(target_os = "linux")]
+
+mod helpers;
+
+#[test]
+fn test_kill_on_parent_death() {
+    use std::os::unix::process::ExitStatusExt as _;
+
+    // the parent death signal is sent when the thread which spawned the
+    // child exits (see prctl(2)), so spawning from a separate thread lets
+    // us observe it while still being able to wait for the child. the pty
+    // is kept open, since closing it would send the child SIGHUP.
+    let (_pty, mut child) = std::thread::spawn(|| {
+        let pty = pty_process::blocking::Pty::new().unwrap();
+        let pts = pty.pts().unwrap();
+        let mut cmd = pty_process::blocking::Command::new("sleep");
+        cmd.arg("1000").kill_on_parent_death(libc::SIGKILL);
+        let child = cmd.spawn(&pts).unwrap();
+        (pty, child)
+    })
+    .join()
+    .unwrap();
+
+    nix::unistd::alarm::set(5);
+    let status = child.wait().unwrap();
+    nix::unistd::alarm::cancel();
+    assert_eq!(status.signal(), Some(libc::SIGKILL));
+}
+
+#[test]
+fn test_kill_on_parent_death_invalid_signal() {
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let mut cmd = pty_process::blocking::Command::new("true");
+    cmd.kill_on_parent_death(-1);
+    let err = cmd.spawn(&pts).unwrap_err();
+    assert_eq!(
+        err.operation(),
+        Some(pty_process::Operation::ParentDeathSignal)
+    );
+    assert!(err.pid().is_some());
+}
+
+const PARENT_ENV: &str = "PTY_PROCESS_TEST_PDEATHSIG_PARENT";
+
+#[test]
+fn test_kill_on_parent_death_parent_exited() {
+    if std::env::var_os(PARENT_ENV).is_some() {
+        exiting_parent();
+        return;
+    }
+    helpers::in_separate_process(
+        "test_kill_on_parent_death_parent_exited",
+        kill_on_parent_death_parent_exited,
+    );
+}
+
+fn kill_on_parent_death_parent_exited() {
+    use std::os::unix::process::ExitStatusExt as _;
+
+    // the child is orphaned once its parent exits, so make sure it is
+    // reparented to us, so that we can wait for it
+    assert_eq!(unsafe { libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1) }, 0);
+    let status = std::process::Command::new(std::env::current_exe().unwrap())
+        .args([
+            "--exact",
+            "test_kill_on_parent_death_parent_exited",
+            "--nocapture",
+        ])
+        .env(PARENT_ENV, "1")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // the parent death signal was only set up after the parent had exited,
+    // so the kernel never sends it, and the child has to notice that its
+    // parent is gone by itself. otherwise, it would exec sleep.
+    nix::unistd::alarm::set(5);
+    let mut status = 0;
+    assert!(unsafe { libc::waitpid(-1, &mut status, 0) } > 0);
+    nix::unistd::alarm::cancel();
+    assert_eq!(
+        std::process::ExitStatus::from_raw(status).signal(),
+        Some(libc::SIGKILL)
+    );
+}
+
+static PARENT: std::sync::atomic::AtomicI32 =
+    std::sync::atomic::AtomicI32::new(0);
+static STOPPED: std::sync::atomic::AtomicI32 =
+    std::sync::atomic::AtomicI32::new(-1);
+
+// spawns a child, and exits while the child is partway through its setup.
+// a seccomp filter makes the umask call which happens right before the
+// parent death signal is set raise SIGSYS in the child instead, and the
+// handler (inherited from this process) reports that it was called and
+// then waits for this process to exit.
+fn exiting_parent() {
+    extern "C" fn handle_sigsys(_: i32) {
+        use std::sync::atomic::Ordering;
+
+        unsafe {
+            libc::write(
+                STOPPED.load(Ordering::SeqCst),
+                [0u8].as_ptr().cast(),
+                1,
+            );
+            while libc::getppid() == PARENT.load(Ordering::SeqCst) {
+                libc::usleep(1000);
+            }
+        }
+    }
+
+    let (stopped_r, stopped_w) = nix::unistd::pipe().unwrap();
+    PARENT.store(
+        i32::try_from(std::process::id()).unwrap(),
+        std::sync::atomic::Ordering::SeqCst,
+    );
+    STOPPED.store(stopped_w, std::sync::atomic::Ordering::SeqCst);
+    // Safety: the handler only makes async-signal-safe calls
+    unsafe {
+        nix::sys::signal::signal(
+            nix::sys::signal::Signal::SIGSYS,
+            nix::sys::signal::SigHandler::Handler(handle_sigsys),
+        )
+    }
+    .unwrap();
+
+    let filter = [
+        // load the syscall number
+        libc::sock_filter {
+            code: u16::try_from(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS)
+                .unwrap(),
+            jt: 0,
+            jf: 0,
+            k: 0,
+        },
+        libc::sock_filter {
+            code: u16::try_from(libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K)
+                .unwrap(),
+            jt: 0,
+            jf: 1,
+            k: u32::try_from(libc::SYS_umask).unwrap(),
+        },
+        libc::sock_filter {
+            code: u16::try_from(libc::BPF_RET | libc::BPF_K).unwrap(),
+            jt: 0,
+            jf: 0,
+            k: libc::SECCOMP_RET_TRAP,
+        },
+        libc::sock_filter {
+            code: u16::try_from(libc::BPF_RET | libc::BPF_K).unwrap(),
+            jt: 0,
+            jf: 0,
+            k: libc::SECCOMP_RET_ALLOW,
+        },
+    ];
+    let prog = libc::sock_fprog {
+        len: u16::try_from(filter.len()).unwrap(),
+        filter: filter.as_ptr().cast_mut(),
+    };
+    // the filter only applies to this thread and its children
+    assert_eq!(
+        unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) },
+        0
+    );
+    assert_eq!(
+        unsafe {
+            libc::syscall(
+                libc::SYS_seccomp,
+                libc::SECCOMP_SET_MODE_FILTER,
+                0,
+                &raw const prog,
+            )
+        },
+        0
+    );
+
+    std::thread::spawn(move || {
+        let mut buf = [0];
+        assert_eq!(nix::unistd::read(stopped_r, &mut buf).unwrap(), 1);
+        unsafe { libc::_exit(0) };
+    });
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let mut cmd = pty_process::blocking::Command::new("sleep");
+    cmd.arg("1000")
+        .umask(0o022)
+        .kill_on_parent_death(libc::SIGKILL);
+    let _ = cmd.spawn(&pts);
+    // the other thread exits the process once the child has stopped
+    loop {
+        std::thread::park();
+    }
+}
@@ -0,0 +1,80 @@
+#![cfg(target_os = "linux")]
+// the children are reaped via their pidfds
+#![allow(clippy::zombie_processes)]
+
+mod helpers;
+
+#[test]
+fn test_pidfd() {
+    use std::os::unix::process::ExitStatusExt as _;
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut cmd = pty_process::blocking::Command::new("sh");
+    cmd.arg("-c").arg("echo foo; sleep 1000");
+    let child = cmd.spawn(&pts).unwrap();
+    let pidfd = pty_process::blocking::Pidfd::new(child).unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "foo\r\n");
+    assert!(pidfd.try_wait().unwrap().is_none());
+
+    pidfd.send_signal(libc::SIGTERM).unwrap();
+    let status = pidfd.wait().unwrap();
+    assert_eq!(status.signal(), Some(libc::SIGTERM));
+
+    // the child has been reaped, and its pid may have been reused, but the
+    // pidfd still refers to the original process
+    assert!(pidfd.send_signal(libc::SIGTERM).is_err());
+    assert!(pidfd.wait().is_err());
+}
+
+#[test]
+fn test_pidfd_exit_code() {
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let mut cmd = pty_process::blocking::Command::new("sh");
+    cmd.arg("-c").arg("exit 3");
+    let child = cmd.spawn(&pts).unwrap();
+    let pidfd = pty_process::blocking::Pidfd::new(child).unwrap();
+    assert_eq!(pidfd.wait().unwrap().code(), Some(3));
+    assert!(pidfd.send_signal(-1).is_err());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_pidfd_async() {
+    use futures::stream::StreamExt as _;
+    use std::os::unix::process::ExitStatusExt as _;
+
+    let pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut cmd = pty_process::Command::new("sh");
+    cmd.arg("-c").arg("echo foo; sleep 1000");
+    let pidfd = cmd.spawn_pidfd(&pts).unwrap();
+
+    let mut output = helpers::output_async(pty);
+    assert_eq!(output.next().await.unwrap(), "foo\r\n");
+    assert!(pidfd.try_wait().unwrap().is_none());
+
+    pidfd.send_signal(libc::SIGKILL).unwrap();
+    let status =
+        tokio::time::timeout(std::time::Duration::from_secs(5), pidfd.wait())
+            .await
+            .unwrap()
+            .unwrap();
+    assert_eq!(status.signal(), Some(libc::SIGKILL));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_pidfd_async_exit_code() {
+    let pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let mut cmd = pty_process::Command::new("sh");
+    cmd.arg("-c").arg("sleep 0.1; exit 3");
+    let pidfd = cmd.spawn_pidfd(&pts).unwrap();
+    assert_eq!(pidfd.wait().await.unwrap().code(), Some(3));
+}
@@ -0,0 +1,115 @@
+mod helpers;
+
+#[test]
+fn test_pipeline() {
+    use std::io::Write as _;
+
+    let mut pty = pty_process::blocking::Pty::new().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let pts = pty.pts().unwrap();
+
+    let mut cmd = pty_process::blocking::Command::new("tr");
+    cmd.args(["a-z", "A-Z"]);
+    let mut children = pty_process::blocking::Pipeline::new()
+        .cmd(pty_process::blocking::Command::new("cat"))
+        .cmd(cmd)
+        .spawn(&pts)
+        .unwrap();
+    assert_eq!(children.len(), 2);
+
+    // the pipeline gets its own process group, but the pty isn't the
+    // controlling terminal of the test process, so nothing tries to make
+    // that group the foreground process group
+    let pgid = children[0].id();
+    assert_ne!(u32::try_from(unsafe { libc::getpgrp() }).unwrap(), pgid);
+    for child in &children {
+        let pid = i32::try_from(child.id()).unwrap();
+        let child_pgid = unsafe { libc::getpgid(pid) };
+        assert_eq!(u32::try_from(child_pgid).unwrap(), pgid);
+    }
+
+    pty.write_all(b"foo\n").unwrap();
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "foo\r\n");
+    assert_eq!(output.next().unwrap(), "FOO\r\n");
+
+    // ^D closes cat's stdin, and tr sees end of file on the pipe once cat
+    // exits
+    pty.write_all(b"\x04").unwrap();
+    for child in &mut children {
+        assert!(child.wait().unwrap().success());
+    }
+}
+
+#[test]
+fn test_pipeline_overridden() {
+    use std::io::Read as _;
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let pts = pty.pts().unwrap();
+
+    let (r, w) = nix::unistd::pipe().unwrap();
+    // Safety: these fds were just created and are owned by nothing else
+    let (mut r, w) = unsafe {
+        use std::os::fd::FromRawFd as _;
+        (std::fs::File::from_raw_fd(r), std::fs::File::from_raw_fd(w))
+    };
+
+    let mut first = pty_process::blocking::Command::new("sh");
+    first.arg("-c").arg("echo foo; echo bar >&2");
+    let mut second = pty_process::blocking::Command::new("tr");
+    second.args(["a-z", "A-Z"]).stdout(w);
+    let mut children = pty_process::blocking::Pipeline::new()
+        .cmd(first)
+        .cmd(second)
+        .spawn(&pts)
+        .unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "bar\r\n");
+    for child in &mut children {
+        assert!(child.wait().unwrap().success());
+    }
+    drop(children);
+    let mut buf = String::new();
+    r.read_to_string(&mut buf).unwrap();
+    assert_eq!(buf, "FOO\n");
+}
+
+#[test]
+fn test_pipeline_empty() {
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    assert!(pty_process::blocking::Pipeline::new().spawn(&pts).is_err());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_pipeline_async() {
+    use futures::stream::StreamExt as _;
+    use tokio::io::AsyncWriteExt as _;
+
+    let mut pty = pty_process::Pty::new().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let pts = pty.pts().unwrap();
+
+    let mut cmd = pty_process::Command::new("tr");
+    cmd.args(["a-z", "A-Z"]);
+    let mut children = pty_process::Pipeline::new()
+        .cmd(pty_process::Command::new("cat"))
+        .cmd(cmd)
+        .spawn(&pts)
+        .unwrap();
+
+    let (pty_r, mut pty_w) = pty.split();
+    pty_w.write_all(b"foo\n").await.unwrap();
+    let mut output = helpers::output_async(pty_r);
+    assert_eq!(output.next().await.unwrap(), "foo\r\n");
+    assert_eq!(output.next().await.unwrap(), "FOO\r\n");
+
+    pty_w.write_all(b"\x04").await.unwrap();
+    for child in &mut children {
+        assert!(child.wait().await.unwrap().success());
+    }
+}
@@ -1,5 +1,70 @@
 mod helpers;
 
+#[test]
+fn test_into_split_blocking() {
+    use std::io::{BufRead as _, Write as _};
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut cmd = pty_process::blocking::Command::new("perl");
+    cmd.args(["-plE", "BEGIN { $SIG{WINCH} = sub { say 'WINCH' } }"]);
+    let mut child = cmd.spawn(&pts).unwrap();
+
+    let (mut pty_r, mut pty_w) = pty.into_split();
+    pty_r.set_read_timeout(Some(std::time::Duration::from_secs(5)));
+
+    let reader = std::thread::spawn(move || {
+        let mut ptybuf = std::io::BufReader::new(pty_r);
+        for _ in 0..2 {
+            let mut buf = vec![];
+            ptybuf.read_until(b'\n', &mut buf).unwrap();
+            assert_eq!(&buf[..], b"foo\r\n");
+        }
+        ptybuf.into_inner()
+    });
+    pty_w.write_all(b"foo\n").unwrap();
+    let pty_r = reader.join().unwrap();
+
+    pty_w.resize(pty_process::Size::new(25, 80)).unwrap();
+    let mut ptybuf = std::io::BufReader::new(pty_r);
+    let mut buf = vec![];
+    ptybuf.read_until(b'\n', &mut buf).unwrap();
+    assert_eq!(&buf[..], b"WINCH\r\n");
+
+    let mut pty = ptybuf.into_inner().unsplit(pty_w).unwrap();
+    assert_eq!(pty.read_timeout(), Some(std::time::Duration::from_secs(5)));
+    pty.write_all(&[4u8]).unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn test_into_split_error_blocking() {
+    let pty1 = pty_process::blocking::Pty::new().unwrap();
+    let pty2 = pty_process::blocking::Pty::new().unwrap();
+
+    let (pty1_r, pty1_w) = pty1.into_split();
+    let (pty2_r, pty2_w) = pty2.into_split();
+
+    let (pty1_r, pty2_w) = if let Err(pty_process::UnsplitError(r, w)) =
+        pty1_r.unsplit(pty2_w)
+    {
+        (r, w)
+    } else {
+        panic!("fail");
+    };
+    let (pty2_r, pty1_w) = if let Err(pty_process::UnsplitError(r, w)) =
+        pty2_r.unsplit(pty1_w)
+    {
+        (r, w)
+    } else {
+        panic!("fail");
+    };
+
+    let _pty1 = pty1_r.unsplit(pty1_w).unwrap();
+    let _pty2 = pty2_r.unsplit(pty2_w).unwrap();
+}
+
 #[cfg(feature = "async")]
 #[tokio::test]
 async fn test_split() {
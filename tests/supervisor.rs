@@ -0,0 +1,110 @@
+#![cfg(target_os = "linux")]
+
+mod helpers;
+
+#[test]
+fn test_supervisor() {
+    helpers::in_separate_process("test_supervisor", supervisor);
+}
+
+fn supervisor() {
+    let supervisor = pty_process::blocking::Supervisor::new().unwrap();
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut cmd = pty_process::blocking::Command::new("sh");
+    cmd.arg("-c").arg("(sleep 0.5; echo bar) & echo foo");
+    let start = std::time::Instant::now();
+    let child = cmd.spawn(&pts).unwrap();
+    let pid = child.id();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "foo\r\n");
+
+    // the background process is orphaned when the shell exits, but is
+    // still part of the session
+    let status = supervisor.wait(child).unwrap();
+    assert!(start.elapsed() >= std::time::Duration::from_millis(500));
+    assert!(status.leader().success());
+    assert_eq!(status.descendants().len(), 1);
+    assert_ne!(status.descendants()[0].0, pid);
+    assert!(status.descendants()[0].1.success());
+    assert_eq!(output.next().unwrap(), "bar\r\n");
+}
+
+#[test]
+fn test_supervisor_other_children() {
+    helpers::in_separate_process(
+        "test_supervisor_other_children",
+        supervisor_other_children,
+    );
+}
+
+fn supervisor_other_children() {
+    let supervisor = pty_process::blocking::Supervisor::new().unwrap();
+
+    // not part of the supervised session, so it must be left for us to
+    // wait for
+    let mut other = std::process::Command::new("true").spawn().unwrap();
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let mut cmd = pty_process::blocking::Command::new("sleep");
+    cmd.arg("0.2");
+    let child = cmd.spawn(&pts).unwrap();
+    let status = supervisor.wait(child).unwrap();
+    assert!(status.leader().success());
+    assert!(status.descendants().is_empty());
+
+    assert!(other.wait().unwrap().success());
+}
+
+#[test]
+fn test_supervisor_not_session_leader() {
+    helpers::in_separate_process(
+        "test_supervisor_not_session_leader",
+        supervisor_not_session_leader,
+    );
+}
+
+fn supervisor_not_session_leader() {
+    let supervisor = pty_process::blocking::Supervisor::new().unwrap();
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let mut cmd = pty_process::blocking::Command::new("sleep");
+    cmd.arg("0.1")
+        .session_mode(pty_process::SessionMode::NewProcessGroup);
+    let child = cmd.spawn(&pts).unwrap();
+    let pid = i32::try_from(child.id()).unwrap();
+    assert!(supervisor.wait(child).is_err());
+    let mut status = 0;
+    assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+}
+
+#[test]
+fn test_supervisor_setsid() {
+    helpers::in_separate_process("test_supervisor_setsid", supervisor_setsid);
+}
+
+fn supervisor_setsid() {
+    let supervisor = pty_process::blocking::Supervisor::new().unwrap();
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    let mut cmd = pty_process::blocking::Command::new("sh");
+    // the first background process (which has the lower pid) leaves the
+    // session while wait is running, and then keeps running for longer
+    // than the test takes
+    cmd.arg("-c")
+        .arg("(sleep 0.2; exec setsid sleep 10) & sleep 0.5 & exit 0");
+    let start = std::time::Instant::now();
+    let child = cmd.spawn(&pts).unwrap();
+    let status = supervisor.wait(child).unwrap();
+    assert!(start.elapsed() >= std::time::Duration::from_millis(500));
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    assert!(status.leader().success());
+    assert_eq!(status.descendants().len(), 1);
+    assert!(status.descendants()[0].1.success());
+}
@@ -0,0 +1,173 @@
+mod helpers;
+
+#[derive(Default)]
+struct Recorder {
+    read: std::sync::Mutex<Vec<u8>>,
+    written: std::sync::Mutex<Vec<u8>>,
+    times: std::sync::Mutex<Vec<std::time::Instant>>,
+}
+
+impl pty_process::Tap for Recorder {
+    fn on_read(&self, chunk: pty_process::Chunk<'_>) {
+        self.read.lock().unwrap().extend_from_slice(chunk.data());
+        self.times.lock().unwrap().push(chunk.time());
+    }
+
+    fn on_write(&self, chunk: pty_process::Chunk<'_>) {
+        self.written.lock().unwrap().extend_from_slice(chunk.data());
+        self.times.lock().unwrap().push(chunk.time());
+    }
+}
+
+#[test]
+fn test_tap_blocking() {
+    use std::io::Write as _;
+
+    let recorder = std::sync::Arc::new(Recorder::default());
+    let start = std::time::Instant::now();
+
+    let mut pty = pty_process::blocking::Pty::new().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    pty.add_tap(recorder.clone());
+    let mut child = pty_process::blocking::Command::new("cat")
+        .spawn(&pty.pts().unwrap())
+        .unwrap();
+
+    let mut output = helpers::output(&pty);
+
+    (&pty).write_all(b"foo\n").unwrap();
+    assert_eq!(output.next().unwrap(), "foo\r\n");
+    assert_eq!(output.next().unwrap(), "foo\r\n");
+
+    let bytes = (&pty)
+        .write_vectored(&[
+            std::io::IoSlice::new(b"b"),
+            std::io::IoSlice::new(b"ar\n"),
+        ])
+        .unwrap();
+    assert_eq!(bytes, 4);
+    assert_eq!(output.next().unwrap(), "bar\r\n");
+    assert_eq!(output.next().unwrap(), "bar\r\n");
+
+    pty.write_all(&[4u8]).unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    assert_eq!(*recorder.written.lock().unwrap(), b"foo\nbar\n\x04");
+    assert_eq!(
+        *recorder.read.lock().unwrap(),
+        b"foo\r\nfoo\r\nbar\r\nbar\r\n"
+    );
+    let times = recorder.times.lock().unwrap();
+    assert!(times.first().unwrap() >= &start);
+    assert!(times.windows(2).all(|w| w[0] <= w[1]));
+    assert!(times.last().unwrap() <= &std::time::Instant::now());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_tap_async() {
+    use futures::stream::StreamExt as _;
+    use tokio::io::AsyncWriteExt as _;
+
+    let recorder1 = std::sync::Arc::new(Recorder::default());
+    let recorder2 = std::sync::Arc::new(Recorder::default());
+
+    let pty = pty_process::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    pty.add_tap(recorder1.clone());
+    let mut child = pty_process::Command::new("cat").spawn(&pts).unwrap();
+
+    let (pty_r, mut pty_w) = pty.into_split();
+    pty_r.add_tap(recorder2.clone());
+
+    pty_w.write_all(b"foo\n").await.unwrap();
+
+    let mut output = helpers::output_async(pty_r);
+    assert_eq!(output.next().await.unwrap(), "foo\r\n");
+    assert_eq!(output.next().await.unwrap(), "foo\r\n");
+
+    pty_w.write_all(&[4u8]).await.unwrap();
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    for recorder in [recorder1, recorder2] {
+        assert_eq!(*recorder.written.lock().unwrap(), b"foo\n\x04");
+        assert_eq!(*recorder.read.lock().unwrap(), b"foo\r\nfoo\r\n");
+    }
+}
+
+#[test]
+fn test_remove_tap() {
+    use std::io::Write as _;
+
+    let recorder1 = std::sync::Arc::new(Recorder::default());
+    let recorder2 = std::sync::Arc::new(Recorder::default());
+
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let id1 = pty.add_tap(recorder1.clone());
+    let id2 = pty.add_tap(recorder2.clone());
+    assert_ne!(id1, id2);
+    let mut child = pty_process::blocking::Command::new("cat")
+        .spawn(&pty.pts().unwrap())
+        .unwrap();
+
+    let mut output = helpers::output(&pty);
+
+    (&pty).write_all(b"foo\n").unwrap();
+    assert_eq!(output.next().unwrap(), "foo\r\n");
+    assert_eq!(output.next().unwrap(), "foo\r\n");
+
+    let (pty_r, mut pty_w) = pty.into_split();
+    assert!(pty_r.remove_tap(id1));
+    assert!(!pty_w.remove_tap(id1));
+
+    pty_w.write_all(b"bar\n").unwrap();
+    pty_w.write_all(&[4u8]).unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    assert_eq!(*recorder1.written.lock().unwrap(), b"foo\n");
+    assert_eq!(*recorder2.written.lock().unwrap(), b"foo\nbar\n\x04");
+    drop(pty_r);
+}
+
+#[derive(Default)]
+struct RemoveSelf {
+    pty: std::sync::OnceLock<std::sync::Weak<pty_process::blocking::Pty>>,
+    id: std::sync::OnceLock<pty_process::TapId>,
+    calls: std::sync::atomic::AtomicUsize,
+}
+
+impl pty_process::Tap for RemoveSelf {
+    fn on_write(&self, _chunk: pty_process::Chunk<'_>) {
+        self.calls
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let pty = self.pty.get().unwrap().upgrade().unwrap();
+        assert!(pty.remove_tap(*self.id.get().unwrap()));
+    }
+}
+
+#[test]
+fn test_remove_tap_from_tap() {
+    use std::io::Write as _;
+
+    let tap = std::sync::Arc::new(RemoveSelf::default());
+
+    let pty = std::sync::Arc::new(pty_process::blocking::Pty::new().unwrap());
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    tap.pty.set(std::sync::Arc::downgrade(&pty)).unwrap();
+    tap.id.set(pty.add_tap(tap.clone())).unwrap();
+    let mut child = pty_process::blocking::Command::new("cat")
+        .spawn(&pty.pts().unwrap())
+        .unwrap();
+
+    (&*pty).write_all(b"foo\n").unwrap();
+    (&*pty).write_all(&[4u8]).unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    assert_eq!(tap.calls.load(std::sync::atomic::Ordering::Relaxed), 1);
+}
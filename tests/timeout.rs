@@ -0,0 +1,119 @@
+#[test]
+fn test_read_timeout() {
+    use std::io::{Read as _, Write as _};
+
+    let mut pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    pty.set_read_timeout(Some(std::time::Duration::from_millis(100)));
+    let mut child = pty_process::blocking::Command::new("cat")
+        .spawn(&pts)
+        .unwrap();
+
+    let mut buf = [0u8; 1024];
+    let start = std::time::Instant::now();
+    let err = pty.read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    assert!(start.elapsed() >= std::time::Duration::from_millis(100));
+
+    pty.write_all(b"foo\n").unwrap();
+    let bytes = pty.read(&mut buf).unwrap();
+    assert!(b"foo\r\nfoo\r\n".starts_with(&buf[..bytes]));
+
+    pty.write_all(&[4u8]).unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_read_with_deadline() {
+    use std::io::Write as _;
+
+    let mut pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("cat")
+        .spawn(&pts)
+        .unwrap();
+
+    let mut buf = [0u8; 1024];
+    let deadline =
+        std::time::Instant::now() + std::time::Duration::from_millis(100);
+    let err = pty.read_with_deadline(&mut buf, deadline).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    assert!(std::time::Instant::now() >= deadline);
+
+    pty.write_all(b"foo\n").unwrap();
+    let deadline =
+        std::time::Instant::now() + std::time::Duration::from_secs(5);
+    let bytes = pty.read_with_deadline(&mut buf, deadline).unwrap();
+    assert!(b"foo\r\nfoo\r\n".starts_with(&buf[..bytes]));
+
+    pty.write_all(&[4u8]).unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_read_with_deadline_concurrent() {
+    let pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("sh")
+        .arg("-c")
+        .arg("echo foo; sleep 2")
+        .spawn(&pts)
+        .unwrap();
+
+    // both threads can see the pty become readable, but only one of them
+    // gets the data, and the other one must still time out rather than
+    // blocking in read
+    let deadline =
+        std::time::Instant::now() + std::time::Duration::from_millis(500);
+    let read = || {
+        let mut buf = [0u8; 1024];
+        pty.read_with_deadline(&mut buf, deadline)
+            .map(|bytes| buf[..bytes].to_vec())
+    };
+    let (a, b) = std::thread::scope(|s| {
+        let a = s.spawn(read);
+        let b = s.spawn(read);
+        (a.join().unwrap(), b.join().unwrap())
+    });
+    assert!(
+        std::time::Instant::now()
+            < deadline + std::time::Duration::from_secs(1)
+    );
+    let (data, err) = if a.is_ok() { (a, b) } else { (b, a) };
+    assert_eq!(data.unwrap(), b"foo\r\n");
+    assert_eq!(err.unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn test_write_timeout() {
+    use std::io::Write as _;
+
+    let mut pty = pty_process::blocking::Pty::new().unwrap();
+    let pts = pty.pts().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    pty.set_write_timeout(Some(std::time::Duration::from_millis(100)));
+    let mut child = pty_process::blocking::Command::new("sleep")
+        .arg("10")
+        .spawn(&pts)
+        .unwrap();
+
+    // nothing is reading from the pts, so this will eventually fill the
+    // input buffer
+    let err = loop {
+        if let Err(e) = pty.write_all(b"foo\n") {
+            break e;
+        }
+    };
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
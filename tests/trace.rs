@@ -0,0 +1,116 @@
+#![cfg(feature = "tracing")]
+
+#[derive(Default)]
+struct Recorder {
+    events: std::sync::Mutex<Vec<String>>,
+}
+
+impl Recorder {
+    fn find(&self, message: &str) -> Option<String> {
+        let message = format!("message={message} ");
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|event| event.starts_with(&message))
+            .cloned()
+    }
+}
+
+struct Fields(String);
+
+impl tracing::field::Visit for Fields {
+    fn record_debug(
+        &mut self,
+        field: &tracing::field::Field,
+        value: &dyn std::fmt::Debug,
+    ) {
+        self.0.push_str(&format!("{}={:?} ", field.name(), value));
+    }
+}
+
+impl tracing::Subscriber for Recorder {
+    fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(
+        &self,
+        _: &tracing::span::Attributes<'_>,
+    ) -> tracing::span::Id {
+        tracing::span::Id::from_u64(1)
+    }
+
+    fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+    fn record_follows_from(
+        &self,
+        _: &tracing::span::Id,
+        _: &tracing::span::Id,
+    ) {
+    }
+
+    fn event(&self, event: &tracing::Event<'_>) {
+        let mut fields = Fields(String::new());
+        event.record(&mut fields);
+        self.events.lock().unwrap().push(fields.0);
+    }
+
+    fn enter(&self, _: &tracing::span::Id) {}
+
+    fn exit(&self, _: &tracing::span::Id) {}
+}
+
+#[test]
+fn test_trace_spawn() {
+    use std::io::Read as _;
+
+    let recorder = std::sync::Arc::new(Recorder::default());
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let mut pty = pty_process::blocking::Pty::new().unwrap();
+        pty.resize(pty_process::Size::new(24, 80)).unwrap();
+        let pts = pty.pts().unwrap();
+        let mut child = pty_process::blocking::Command::new("echo")
+            .arg("foo")
+            .spawn(&pts)
+            .unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        drop(pts);
+        let mut buf = vec![];
+        pty.read_to_end(&mut buf).unwrap();
+
+        let allocated = recorder.find("allocated pty").unwrap();
+        let name = allocated
+            .strip_prefix("message=allocated pty pty=")
+            .unwrap()
+            .trim_end();
+        assert!(name.starts_with("/dev/"));
+
+        let resized = recorder.find("resizing pty").unwrap();
+        assert!(resized.contains(&format!("pty={name} ")));
+        assert!(resized.contains("rows=24 cols=80"));
+        assert!(recorder.find("opened pts").is_some());
+        let spawned = recorder.find("spawned child").unwrap();
+        assert!(spawned.contains(&format!("pid={pid}")));
+        let read = recorder.find("read").unwrap();
+        assert!(read.contains("bytes=5"));
+    });
+}
+
+#[test]
+fn test_trace_spawn_failure() {
+    let recorder = std::sync::Arc::new(Recorder::default());
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let pty = pty_process::blocking::Pty::new().unwrap();
+        let pts = pty.pts().unwrap();
+        let err = pty_process::blocking::Command::new("/nonexistent")
+            .spawn(&pts)
+            .unwrap_err();
+        assert_eq!(err.operation(), Some(pty_process::Operation::Spawn));
+
+        let failed = recorder.find("operation failed").unwrap();
+        assert!(failed.contains("operation=spawn "));
+        assert!(failed.contains("error="));
+    });
+}
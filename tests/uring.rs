@@ -0,0 +1,147 @@
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+async fn read_until_len(
+    pty: &pty_process::uring::ReadPty<'_>,
+    len: usize,
+) -> Vec<u8> {
+    let mut output = vec![];
+    while output.len() < len {
+        let (res, buf) = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            pty.read(Vec::with_capacity(1024)),
+        )
+        .await
+        .unwrap();
+        let bytes = res.unwrap();
+        assert!(bytes > 0);
+        output.extend_from_slice(&buf[..bytes]);
+    }
+    output
+}
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+#[test]
+fn test_cat_uring() {
+    tokio_uring::start(async {
+        let mut pty = pty_process::uring::Pty::new().unwrap();
+        let pts = pty.pts().unwrap();
+        pty.resize(pty_process::Size::new(24, 80)).unwrap();
+        let mut child = pty_process::Command::new("cat").spawn(&pts).unwrap();
+
+        let (pty_r, pty_w) = pty.split();
+
+        let (res, _) = pty_w.write_all(b"foo\n".to_vec()).await;
+        res.unwrap();
+        assert_eq!(read_until_len(&pty_r, 10).await, b"foo\r\nfoo\r\n");
+
+        let (res, _) = pty_w.write_all(vec![4u8]).await;
+        res.unwrap();
+        let status = child.wait().await.unwrap();
+        assert_eq!(status.code().unwrap(), 0);
+
+        drop(pts);
+        let (res, _) = pty_r.read(Vec::with_capacity(1024)).await;
+        assert_eq!(res.unwrap(), 0);
+        assert!(pty_r.is_hung_up());
+    });
+}
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+#[test]
+fn test_fixed_uring() {
+    use tokio_uring::buf::BoundedBufMut as _;
+
+    tokio_uring::start(async {
+        let registry = tokio_uring::buf::fixed::FixedBufRegistry::new(
+            std::iter::repeat_with(|| Vec::with_capacity(1024)).take(2),
+        );
+        registry.register().unwrap();
+
+        let pty = pty_process::uring::Pty::new().unwrap();
+        let pts = pty.pts().unwrap();
+        pty.resize(pty_process::Size::new(24, 80)).unwrap();
+        let mut child = pty_process::Command::new("cat").spawn(&pts).unwrap();
+
+        let (pty_r, pty_w) = pty.into_split();
+
+        let mut write_buf = registry.check_out(0).unwrap();
+        write_buf.put_slice(b"foo\n");
+        let (res, _) = pty_w.write_fixed_all(write_buf).await;
+        res.unwrap();
+
+        let mut output = vec![];
+        let mut read_buf = registry.check_out(1).unwrap();
+        while output.len() < 10 {
+            let (res, buf) = tokio::time::timeout(
+                std::time::Duration::from_secs(5),
+                pty_r.read_fixed(read_buf),
+            )
+            .await
+            .unwrap();
+            read_buf = buf;
+            let bytes = res.unwrap();
+            assert!(bytes > 0);
+            output.extend_from_slice(&read_buf[..bytes]);
+        }
+        assert_eq!(output, b"foo\r\nfoo\r\n");
+
+        let (res, _) = pty_w.write_all(vec![4u8]).await;
+        res.unwrap();
+        let status = child.wait().await.unwrap();
+        assert_eq!(status.code().unwrap(), 0);
+
+        let _pty = pty_r.unsplit(pty_w).unwrap();
+    });
+}
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+#[test]
+fn test_into_split_error_uring() {
+    let pty1 = pty_process::uring::Pty::new().unwrap();
+    let pty2 = pty_process::uring::Pty::new().unwrap();
+
+    let (pty1_r, pty1_w) = pty1.into_split();
+    let (pty2_r, pty2_w) = pty2.into_split();
+
+    let Err(pty_process::UnsplitError(pty1_r, pty2_w)) =
+        pty1_r.unsplit(pty2_w)
+    else {
+        panic!("fail");
+    };
+    let Err(pty_process::UnsplitError(pty2_r, pty1_w)) =
+        pty2_r.unsplit(pty1_w)
+    else {
+        panic!("fail");
+    };
+
+    let _pty1 = pty1_r.unsplit(pty1_w).unwrap();
+    let _pty2 = pty2_r.unsplit(pty2_w).unwrap();
+}
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+#[test]
+fn test_hangup_uring() {
+    tokio_uring::start(async {
+        let pty = pty_process::uring::Pty::new().unwrap();
+        let pts = pty.pts().unwrap();
+        pty.resize(pty_process::Size::new(24, 80)).unwrap();
+        let mut child = pty_process::Command::new("cat").spawn(&pts).unwrap();
+        drop(pts);
+
+        let hangup = tokio::spawn(pty.hangup());
+
+        let (res, _) = pty.write_all(b"foo\n".to_vec()).await;
+        res.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(!hangup.is_finished());
+
+        let (res, _) = pty.write_all(vec![4u8]).await;
+        res.unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(5), hangup)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let status = child.wait().await.unwrap();
+        assert_eq!(status.code().unwrap(), 0);
+    });
+}